@@ -0,0 +1,77 @@
+//! 量測 ACO 每秒跑幾代，以及每次複製 `Solution` 的配置次數及位元組數
+//!
+//! 以 `cargo bench --bench aco_epoch` 執行。
+//!
+//! `Solution` 改為寫入時複製及 bitset 前後，在同一台機器上各跑三次的結果：
+//! 每次複製由 11.2 μs、54 次配置、18770 B 降為 3.6 μs、8 次配置、6878 B；
+//! ACO 由每秒 351–370 代增加為 414–436 代。
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use adams_leaf::algorithm::AlgorithmEnum;
+use adams_leaf::cnc::CNC;
+use adams_leaf::utils::yaml;
+
+const TIMEOUT: u64 = 5_000_000;
+const CLONES: usize = 10_000;
+
+/// 計算配置次數及位元組數的配置器
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn main() {
+    let (tsns1, avbs1) = yaml::load_streams("data/streams/motiv-heavy.yaml", 1);
+    let (tsns2, avbs2) = yaml::load_streams("data/streams/motiv-reconf.yaml", 2);
+    let network = yaml::load_network("data/network/typical.yaml");
+
+    let mut config = yaml::load_config("data/config/default.yaml");
+    config.algorithm = String::from("aco");
+    config.early_stop = false;
+    config.parameters.threads = 1;
+
+    let mut cnc = CNC::new(network, config);
+    cnc.add_streams(tsns1, avbs1);
+    cnc.configure();
+    cnc.add_streams(tsns2, avbs2);
+
+    let (allocations, bytes) = (ALLOCATIONS.load(Ordering::Relaxed), BYTES.load(Ordering::Relaxed));
+    let start = Instant::now();
+    let clones = (0..CLONES).map(|_| cnc.solution.clone()).collect::<Vec<_>>();
+    let elapsed = start.elapsed();
+    drop(clones);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = BYTES.load(Ordering::Relaxed) - bytes;
+    println!("--- solution clone: {:.0} ns, {:.1} allocations, {:.0} bytes ---",
+             elapsed.as_nanos() as f64 / CLONES as f64,
+             allocations as f64 / CLONES as f64, bytes as f64 / CLONES as f64);
+
+    cnc.config.timeout = TIMEOUT;
+    let elapsed = cnc.configure();
+    if let AlgorithmEnum::ACO(aco) = &cnc.algorithm {
+        let rate = aco.epochs() as f64 / (elapsed as f64 / 1e6);
+        println!("--- aco: {:.2} epochs of {} ants per second ---", rate, cnc.config.parameters.m);
+    }
+}
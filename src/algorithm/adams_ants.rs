@@ -1,10 +1,15 @@
+use std::iter;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, Scope};
 use std::time::Instant;
 
 use rand::prelude::SliceRandom;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 
-use super::base::ants::{Ant, AntColony};
+use super::base::ants::{Ant, AntColony, Deposit};
 use super::base::yens::Yens;
 use super::Algorithm;
 use crate::cnc::Toolbox;
@@ -18,16 +23,44 @@ pub struct ACO {
     yens: Yens,
     mult: Vec<[f64; MAX_K]>,
     seed: u64,
-    param: Parameters,
+    threads: usize,
+    tsn_memory: f64,
+    avb_memory: f64,
+    epochs: usize,
+}
+
+/// 一代螞蟻共用的工作：費洛蒙的快照、出發的解，以及依序領取螞蟻編號的計數器
+struct Epoch {
+    colony: AntColony,
+    base: Solution,
+    epoch: usize,
+    next: AtomicUsize,
+    stop: AtomicBool,
+}
+
+/// 在一次 `configure` 中常駐的執行緒，每一代只傳遞工作而不重新建立執行緒
+struct Crew {
+    jobs: Vec<Sender<Arc<Epoch>>>,
+    results: Receiver<Option<(usize, Ant, bool)>>,
 }
 
 impl ACO {
     pub fn new(network: &Network, seed: u64, param: Parameters) -> Self {
-        let colony = AntColony::new(0, MAX_K, None);
+        param.validate();
+        let colony = AntColony::new(0, MAX_K, &param);
         let mut yens = Yens::new(network, MAX_K);
         yens.compute(&network);
         let mult = vec![];
-        ACO { colony, yens, mult, seed, param }
+        let threads = match param.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let (tsn_memory, avb_memory) = (param.tsn_memory, param.avb_memory);
+        ACO { colony, yens, mult, seed, threads, tsn_memory, avb_memory, epochs: 0 }
+    }
+    /// 上一次配置跑了幾代
+    pub fn epochs(&self) -> usize {
+        self.epochs
     }
     fn compute_visibility(&self, solution: &Solution, toolbox: &Toolbox) -> Vec<[f64; MAX_K]> {
         // TODO 好好設計能見度函式！
@@ -55,6 +88,71 @@ impl ACO {
     }
 }
 
+impl Crew {
+    fn spawn<'scope>(scope: &'scope Scope<'scope, '_>, threads: usize, seed: u64,
+                     toolbox: &'scope Toolbox) -> Self {
+        let (outbox, results) = mpsc::channel();
+        let jobs = (0..threads)
+            .map(|_| {
+                let (jobs, inbox) = mpsc::channel::<Arc<Epoch>>();
+                let outbox = outbox.clone();
+                scope.spawn(move || {
+                    for job in inbox {
+                        let m = job.colony.m;
+                        // 已有螞蟻達成提早結束的條件時，不再領取新的螞蟻
+                        while !job.stop.load(Ordering::Relaxed) {
+                            let mth = job.next.fetch_add(1, Ordering::Relaxed);
+                            if mth >= m {
+                                break;
+                            }
+                            let stream = job.epoch * m + mth;
+                            let (ant, stop) = construct_ant(&job.colony, &job.base, seed, stream, toolbox);
+                            if stop {
+                                job.stop.store(true, Ordering::Relaxed);
+                            }
+                            outbox.send(Some((mth, ant, stop))).unwrap();
+                        }
+                        outbox.send(None).unwrap();
+                    }
+                });
+                jobs
+            })
+            .collect();
+        Crew { jobs, results }
+    }
+    /// 建構一代的螞蟻，並依編號回傳每隻螞蟻及是否達成提早結束的條件，最多到第一隻達成條件的螞蟻為止
+    ///
+    /// 每隻螞蟻各自使用一條亂數串流，因此結果只和種子有關，與執行緒數無關。
+    /// 提早結束時，編號較小的螞蟻都已領取並建構完成，因此第一隻達成條件的螞蟻也是固定的。
+    fn construct_ants(&self, colony: &AntColony, base: &Solution, epoch: usize) -> Vec<(Ant, bool)> {
+        let job = Arc::new(Epoch {
+            colony: colony.clone(),
+            base: base.clone(),
+            epoch,
+            next: AtomicUsize::new(0),
+            stop: AtomicBool::new(false),
+        });
+        for jobs in self.jobs.iter() {
+            jobs.send(Arc::clone(&job)).unwrap();
+        }
+        let mut ants = vec![];
+        let mut idle = 0;
+        while idle < self.jobs.len() {
+            match self.results.recv().unwrap() {
+                Some(ant) => ants.push(ant),
+                None      => idle += 1,
+            }
+        }
+        ants.sort_by_key(|&(mth, _, _)| mth);
+        if let Some(first) = ants.iter().position(|&(_, _, stop)| stop) {
+            ants.truncate(first + 1);
+        }
+        ants.into_iter()
+            .map(|(_, ant, stop)| (ant, stop))
+            .collect()
+    }
+}
+
 impl Algorithm for ACO {
     fn candidates(&self, src: usize, dst: usize) -> &Vec<Path> {
         self.yens.k_shortest_paths(src.into(), dst.into())
@@ -65,68 +163,92 @@ impl Algorithm for ACO {
         self.mult = vec![[1.0; MAX_K]; flowtable.len()];
         for &tsn in flowtable.tsns() {
             if let Some(kth) = last_run.selection(tsn).current() {
-                self.mult[tsn][kth] = self.param.tsn_memory;
+                self.mult[tsn][kth] = self.tsn_memory;
             }
         }
         for &avb in flowtable.avbs() {
             if let Some(kth) = last_run.selection(avb).current() {
-                self.mult[avb][kth] = self.param.avb_memory;
+                self.mult[avb][kth] = self.avb_memory;
             }
         }
 
         self.colony.heuristic = self.compute_visibility(&last_run, &toolbox);
         self.colony.n = last_run.flowtable().len();
 
-        #[allow(unused_variables)]
         let mut epoch = 0;
-        let mut rng = ChaChaRng::seed_from_u64(self.seed);
-
         let mut global_best = Ant::new(last_run);
         let (cost, _stop) = toolbox.evaluate_cost(&mut global_best.solution);
         global_best.set_distance_from_cost(cost);
 
-        'outer: while Instant::now() < deadline {
-            epoch += 1;
-            let mut iteration_best = Ant::empty();
-
-            for _mth in 0..self.colony.m {
-                let neighbor = global_best.solution.clone();
-                let mut ant = Ant::new(neighbor);
-
-                for nth in 0..self.colony.n {
-                    let kth = select_cluster(&self.colony, nth, &mut rng);
-                    ant.solution.select(nth, kth);
+        let mut stagnant = 0;
+        let threads = self.threads.min(self.colony.m);
+        thread::scope(|scope| {
+            let crew = Crew::spawn(scope, threads, self.seed, &toolbox);
+            while Instant::now() < deadline {
+                epoch += 1;
+                let mut ants = crew.construct_ants(&self.colony, &global_best.solution, epoch);
+                if let Some(position) = ants.iter().position(|(_, stop)| *stop) {
+                    global_best = ants.swap_remove(position).0;
+                    break;
                 }
 
-                let (cost, stop) = toolbox.evaluate_cost(&mut ant.solution);
-                if stop {
-                    global_best = ant;
-                    break 'outer;
-                }
+                let mut ants = ants.into_iter()
+                    .map(|(ant, _)| ant)
+                    .collect::<Vec<_>>();
+                // 成本相同時，較晚找到的排前面
+                ants.reverse();
+                ants.sort_by(|x, y| x.distance.partial_cmp(&y.distance).unwrap());
 
-                ant.set_distance_from_cost(cost);
-                if ant.distance <= iteration_best.distance {
-                    iteration_best = ant;
+                let iteration_best = &ants[0];
+                stagnant = match iteration_best.distance < global_best.distance {
+                    true  => 0,
+                    false => stagnant + 1,
+                };
+                let renewed = iteration_best.distance <= global_best.distance;
+                if renewed {
+                    global_best = iteration_best.clone();
                 }
-            }
 
-            self.colony.evaporate();
-            self.colony.deposit_pheromone(&iteration_best);
-
-            if iteration_best.distance <= global_best.distance {
-                global_best = iteration_best;
+                self.colony.evaporate();
+                // 這一代的最佳解剛成為歷史最佳解時，不讓同一個解留下兩次費洛蒙
+                let ranked: Vec<&Ant> = match self.colony.deposit {
+                    Deposit::IterationBest => ants.iter().collect(),
+                    Deposit::GlobalBest    => iter::once(&global_best)
+                        .chain(ants.iter().skip(renewed as usize))
+                        .collect(),
+                };
+                self.colony.deposit_pheromone(&ranked);
+                if self.colony.stagnation > 0 && stagnant >= self.colony.stagnation {
+                    self.colony.reinitialize();
+                    stagnant = 0;
+                }
+                #[cfg(debug_assertions)]
+                println!("pheromone = {:?}", self.colony.pheromone);
             }
-            #[cfg(debug_assertions)]
-            println!("pheromone = {:?}", self.colony.pheromone);
-        }
+        });
 
         #[cfg(debug_assertions)]
         println!("ACO epoch = {}", epoch);
+        self.epochs = epoch;
 
         global_best.solution
     }
 }
 
+fn construct_ant(colony: &AntColony, base: &Solution, seed: u64, stream: usize,
+                 toolbox: &Toolbox) -> (Ant, bool) {
+    let mut rng = ChaChaRng::seed_from_u64(seed);
+    rng.set_stream(stream as u64);
+    let mut ant = Ant::new(base.clone());
+    for nth in 0..colony.n {
+        let kth = select_cluster(colony, nth, &mut rng);
+        ant.solution.select(nth, kth);
+    }
+    let (cost, stop) = toolbox.evaluate_cost(&mut ant.solution);
+    ant.set_distance_from_cost(cost);
+    (ant, stop)
+}
+
 fn select_cluster(colony: &AntColony, nth: usize, rng: &mut ChaChaRng) -> usize {
     let k = colony.k;
     let q0 = colony.q0;
@@ -140,3 +262,46 @@ fn select_cluster(colony: &AntColony, nth: usize, rng: &mut ChaChaRng) -> usize
                         .map_or(0, |c| c.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnc::CNC;
+    use crate::utils::yaml;
+
+    #[test]
+    fn it_constructs_ants_regardless_of_threads() {
+        let network = yaml::load_network("data/network/typical.yaml");
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.early_stop = false;
+        let (tsns, avbs) = yaml::load_streams("data/streams/motiv-heavy.yaml", 1);
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(tsns, avbs);
+        let len = cnc.flowtable.len();
+        let selections = |cnc: &CNC, threads: usize| {
+            let toolbox = Toolbox::pack(&cnc.scheduler, &cnc.evaluator, &cnc.solution, &cnc.config);
+            let mut aco = ACO::new(&cnc.network, 0, cnc.config.parameters.clone());
+            aco.colony.n = len;
+            aco.colony.resize_pheromone(len);
+            aco.mult = vec![[1.0; MAX_K]; len];
+            aco.colony.heuristic = aco.compute_visibility(&cnc.solution, &toolbox);
+            thread::scope(|scope| {
+                let crew = Crew::spawn(scope, threads, 0, &toolbox);
+                (1..=2)
+                    .flat_map(|epoch| crew.construct_ants(&aco.colony, &cnc.solution, epoch))
+                    .map(|(ant, _)| (0..len)
+                        .map(|nth| ant.solution.selection(nth).next().unwrap())
+                        .collect::<Vec<_>>())
+                    .collect::<Vec<_>>()
+            })
+        };
+        let sequential = selections(&cnc, 1);
+        assert_eq!(sequential.len(), cnc.config.parameters.m * 2);
+        assert_eq!(sequential, selections(&cnc, 7));
+        // every ant is schedulable here, so each epoch stops at its first ant
+        cnc.config.early_stop = true;
+        let sequential = selections(&cnc, 1);
+        assert_eq!(sequential.len(), 2);
+        assert_eq!(sequential, selections(&cnc, 7));
+    }
+}
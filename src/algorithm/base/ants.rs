@@ -1,14 +1,7 @@
 use crate::component::Solution;
+use crate::utils::config::Parameters;
 use crate::MAX_K;
 
-const M: usize = 60;
-const L: usize = 20;
-const TAO0: f64 = 1.0;
-const RHO: f64 = 0.5; // 蒸發率
-const Q0: f64 = 0.0;
-const MAX_PH: f64 = 1.0;
-const MIN_PH: f64 = 0.003;
-
 #[derive(Clone)]
 pub struct Ant {
     pub solution: Solution,
@@ -20,16 +13,21 @@ impl Ant {
         let distance = f64::INFINITY;
         Ant { solution, distance }
     }
-    pub fn empty() -> Self {
-        let solution = Solution::default();
-        let distance = f64::INFINITY;
-        Ant { solution, distance }
-    }
     pub fn set_distance_from_cost(&mut self, cost: f64) {
         self.distance = cost;
     }
 }
 
+/// 每一代由哪些螞蟻留下費洛蒙
+/// * `IterationBest` - 這一代成本最低的 `l` 隻螞蟻
+/// * `GlobalBest` - 歷史最佳解排第一，其後為這一代成本最低的 `l - 1` 隻螞蟻
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Deposit {
+    IterationBest,
+    GlobalBest,
+}
+
+#[derive(Clone)]
 pub struct AntColony {
     pub pheromone: Vec<[f64; MAX_K]>,
     pub heuristic: Vec<[f64; MAX_K]>,
@@ -42,22 +40,30 @@ pub struct AntColony {
     pub q0: f64,
     pub max_ph: f64,
     pub min_ph: f64,
+    pub deposit: Deposit,
+    pub stagnation: usize,
 }
 
 impl AntColony {
-    pub fn new(n: usize, k: usize, tao0: Option<f64>) -> Self {
+    pub fn new(n: usize, k: usize, param: &Parameters) -> Self {
         assert!(k <= MAX_K, "K 值必需在 {} 以下", MAX_K);
-        let tao0 = tao0.unwrap_or(TAO0);
+        let tao0 = param.tao0;
         let pheromone = vec![[tao0; MAX_K]; n];
         let heuristic = vec![[0.0; MAX_K]; n];
         let n = 0;
-        let m = M;
-        let l = L;
-        let rho = RHO;
-        let q0 = Q0;
-        let max_ph = MAX_PH;
-        let min_ph = MIN_PH;
-        AntColony { pheromone, heuristic, n, m, k, l, tao0, rho, q0, max_ph, min_ph }
+        let m = param.m;
+        let l = param.l;
+        let rho = param.rho;
+        let q0 = param.q0;
+        let max_ph = param.max_ph;
+        let min_ph = param.min_ph;
+        let deposit = match param.deposit.as_str() {
+            "iteration_best" => Deposit::IterationBest,
+            "global_best"    => Deposit::GlobalBest,
+            _                => panic!("Failed specify an unknown pheromone deposit"),
+        };
+        let stagnation = param.stagnation;
+        AntColony { pheromone, heuristic, n, m, k, l, tao0, rho, q0, max_ph, min_ph, deposit, stagnation }
     }
     pub fn resize_pheromone(&mut self, new_len: usize) {
         let tao0 = self.tao0;
@@ -72,12 +78,58 @@ impl AntColony {
             }
         }
     }
-    pub fn deposit_pheromone(&mut self, ant: &Ant) {
-        debug_assert!(ant.distance.is_sign_positive());
-        for nth in 0..self.n {
-            let kth = ant.solution.selection(nth).current().unwrap();
-            let pheromone = self.pheromone[nth][kth] + 1.0 / ant.distance;
-            self.pheromone[nth][kth] = f64::min(pheromone, self.max_ph);
+    /// 依排名留下費洛蒙，第 `r` 名（從 0 起算）的份量乘上 `(l - r) / l`
+    /// * `ranked` - 依成本由低到高排序的螞蟻，只取前 `l` 隻
+    pub fn deposit_pheromone(&mut self, ranked: &[&Ant]) {
+        let l = self.l;
+        for (rank, ant) in ranked.iter().take(l).enumerate() {
+            debug_assert!(ant.distance.is_sign_positive());
+            let weight = (l - rank) as f64 / l as f64;
+            for nth in 0..self.n {
+                let kth = ant.solution.selection(nth).current().unwrap();
+                let pheromone = self.pheromone[nth][kth] + weight / ant.distance;
+                self.pheromone[nth][kth] = f64::min(pheromone, self.max_ph);
+            }
         }
     }
+    /// 停滯時將費洛蒙重設回初始值，讓螞蟻重新探索
+    pub fn reinitialize(&mut self) {
+        let tao0 = self.tao0;
+        self.pheromone.iter_mut()
+            .for_each(|pheromone| *pheromone = [tao0; MAX_K]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnc::CNC;
+    use crate::utils::stream::TSN;
+    use crate::utils::yaml;
+
+    #[test]
+    fn it_deposits_by_rank() {
+        let network = yaml::load_network("data/network/trap.yaml");
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.parameters.l = 2;
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(vec![TSN::new(0, 1, 100, 100, 100, 0)], vec![]);
+        let ant = |kth: usize, distance: f64| {
+            let mut ant = Ant::new(cnc.solution.clone());
+            ant.solution.select(0, kth);
+            ant.solution.confirm();
+            ant.set_distance_from_cost(distance);
+            ant
+        };
+        let ranked = [ant(0, 10.0), ant(1, 10.0), ant(1, 1.0)];
+        let mut colony = AntColony::new(1, 2, &cnc.config.parameters);
+        colony.n = 1;
+        colony.pheromone[0] = [0.0; MAX_K];
+        colony.deposit_pheromone(&ranked.iter().collect::<Vec<_>>());
+        // the third ant is beyond the l-best and deposits nothing
+        assert_eq!(colony.pheromone[0][0], 0.1);
+        assert_eq!(colony.pheromone[0][1], 0.05);
+        colony.reinitialize();
+        assert_eq!(colony.pheromone[0][1], colony.tao0);
+    }
 }
@@ -31,8 +31,11 @@ impl Yens {
         if !self.paths[src.index()][dst.index()].is_empty() { return; }
 
         self.dijkstra.compute_root(graph, src);
-        // TODO dump panic message for not connected
-        let shortest = self.dijkstra.shortest_path(src, dst).unwrap();
+        // 不連通的端點之間沒有候選路徑，有資料流經過時由 `FlowTable` 回報
+        let shortest = match self.dijkstra.shortest_path(src, dst) {
+            Some(path) => path,
+            None       => return,
+        };
         let k = self.k;
         let mut list_a = vec![shortest];
         let mut heap_b = MyMinHeap::new();
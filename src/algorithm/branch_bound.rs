@@ -0,0 +1,88 @@
+use std::iter;
+use std::time::Instant;
+
+use super::base::yens::Yens;
+use super::Algorithm;
+use crate::cnc::Toolbox;
+use crate::component::Solution;
+use crate::network::{Network, Path};
+use crate::MAX_K;
+
+// A. H. Land and A. G. Doig, "An Automatic Method of Solving Discrete Programming Problems,"
+// Econometrica, vol. 28, no. 3, pp. 497-520, 1960, doi: 10.2307/1910129.
+
+/// 以深度優先搜尋列舉每條資料流的候選路徑，並以 `Evaluator` 的成本下界剪枝
+///
+/// 搜尋空間隨資料流數呈指數成長，只適用於小型的實例，主要作為衡量其它演算法與最佳解差距的基準。
+/// 為了確定找到最佳解而不理會 early stop；超過時限時則回傳目前找到的最佳解。
+pub struct BNB {
+    yens: Yens,
+    optimal: bool,
+}
+
+/// 目前找到的最佳完整解
+struct Incumbent {
+    solution: Solution,
+    cost: f64,
+}
+
+impl Algorithm for BNB {
+    fn candidates(&self, src: usize, dst: usize) -> &Vec<Path> {
+        self.yens.k_shortest_paths(src.into(), dst.into())
+    }
+    fn configure(&mut self, last_run: Solution, deadline: Instant, toolbox: Toolbox) -> Solution {
+        let mut solution = last_run.clone();
+        let (cost, _) = toolbox.evaluate_cost(&mut solution);
+        let mut incumbent = Incumbent { solution, cost };
+
+        let mut current = last_run;
+        self.optimal = self.branch(0, &mut current, &mut incumbent, deadline, &toolbox);
+
+        #[cfg(debug_assertions)]
+        println!("BNB optimal = {}", self.optimal);
+
+        incumbent.solution
+    }
+}
+
+impl BNB {
+    pub fn new(network: &Network) -> Self {
+        let mut yens = Yens::new(network, MAX_K);
+        yens.compute(network);
+        BNB { yens, optimal: false }
+    }
+    /// 上一次配置是否在時限內搜尋完畢，即回傳的解確實是最佳解
+    pub fn is_optimal(&self) -> bool {
+        self.optimal
+    }
+    /// 決定第 `nth` 條資料流的路徑並繼續往下搜尋，超過時限時回傳 `false`
+    fn branch(&self, nth: usize, current: &mut Solution, incumbent: &mut Incumbent,
+              deadline: Instant, toolbox: &Toolbox) -> bool {
+        let flowtable = current.flowtable();
+        if nth == flowtable.len() {
+            let mut leaf = current.clone();
+            let (cost, _) = toolbox.evaluate_cost(&mut leaf);
+            if cost < incumbent.cost {
+                *incumbent = Incumbent { solution: leaf, cost };
+            }
+            return true;
+        }
+        // 先走上一次的路徑，盡早找到較好的解以便剪枝
+        let first = current.selection(nth).next().unwrap();
+        let others = (0..flowtable.count_candidates(nth)).filter(|&kth| kth != first);
+        for kth in iter::once(first).chain(others) {
+            if Instant::now() > deadline {
+                return false;
+            }
+            current.select(nth, kth);
+            if toolbox.evaluate_lower_bound(current, nth + 1) >= incumbent.cost {
+                continue;
+            }
+            if !self.branch(nth + 1, current, incumbent, deadline, toolbox) {
+                return false;
+            }
+        }
+        current.select(nth, first);
+        true
+    }
+}
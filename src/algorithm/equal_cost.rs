@@ -0,0 +1,143 @@
+use std::time::Instant;
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaChaRng;
+
+use super::base::yens::Yens;
+use super::Algorithm;
+use crate::cnc::Toolbox;
+use crate::component::{FlowTable, Solution};
+use crate::network::{Network, Path};
+use crate::MAX_K;
+
+/// 路徑成本的差距在此範圍內即視為相同
+const COST_TOLERANCE: f64 = 1e-9;
+
+/// 將資料流依雜湊值分散到成本相同的最短路徑上，模擬沒有 CNC 時 ECMP 的行為
+///
+/// 雜湊只和資料流本身的欄位有關，與其在資料流表中的順序無關，因此同一條資料流每次都會得到
+/// 相同的路徑。雜湊以 ChaCha 產生，不同平台及編譯器版本都會得到相同的結果。
+pub struct ECMP {
+    paths: Vec<Vec<Vec<Path>>>,
+    seed: u64,
+}
+
+impl Algorithm for ECMP {
+    fn candidates(&self, src: usize, dst: usize) -> &Vec<Path> {
+        &self.paths[src][dst]
+    }
+    fn configure(&mut self, last_run: Solution, _deadline: Instant, toolbox: Toolbox) -> Solution {
+        let flowtable = last_run.flowtable();
+        let mut solution = last_run;
+        for nth in 0..flowtable.len() {
+            let (src, dst) = flowtable.ends(nth);
+            let kth = self.hash(&flowtable, nth) % self.candidates(src, dst).len();
+            solution.select(nth, kth);
+        }
+        toolbox.evaluate_cost(&mut solution);
+        solution
+    }
+}
+
+impl ECMP {
+    /// * `seed` - 雜湊函數的種子，如同交換器設定不同的雜湊演算法
+    pub fn new(network: &Network, seed: u64) -> Self {
+        let mut yens = Yens::new(network, MAX_K);
+        yens.compute(network);
+        let node_count = network.node_count();
+        let mut paths = vec![vec![vec![]; node_count]; node_count];
+        for &src in &network.end_devices {
+            for &dst in &network.end_devices {
+                if src == dst { continue; }
+                let candidates = yens.k_shortest_paths(src, dst);
+                // 不連通的端點之間沒有路徑，有資料流經過時由 `FlowTable` 回報
+                let shortest = match candidates.first() {
+                    Some(path) => network.duration_along(path, 1),
+                    None       => continue,
+                };
+                paths[src.index()][dst.index()] = candidates.iter()
+                    .take_while(|path| network.duration_along(path, 1) - shortest < COST_TOLERANCE)
+                    .cloned()
+                    .collect();
+            }
+        }
+        ECMP { paths, seed }
+    }
+    /// 以種子及資料流的端點、大小、週期、deadline 與類型作為 ChaCha 的 32 位元組種子
+    fn hash(&self, flowtable: &FlowTable, nth: usize) -> usize {
+        let fields = match flowtable.is_avb(nth) {
+            true  => {
+                let spec = flowtable.avb_spec(nth);
+                [spec.src as u32, spec.dst as u32, spec.size, spec.period, spec.deadline, spec.class as u32]
+            }
+            false => {
+                let spec = flowtable.tsn_spec(nth);
+                [spec.src as u32, spec.dst as u32, spec.size, spec.period, spec.deadline, 0]
+            }
+        };
+        let mut seed = [0; 32];
+        seed[..8].copy_from_slice(&self.seed.to_le_bytes());
+        for (bytes, field) in seed[8..].chunks_exact_mut(4).zip(fields) {
+            bytes.copy_from_slice(&field.to_le_bytes());
+        }
+        ChaChaRng::from_seed(seed).next_u64() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::STP;
+    use crate::cnc::CNC;
+    use crate::utils::stream::AVB;
+    use crate::utils::yaml;
+
+    #[test]
+    fn it_keeps_equal_cost_paths_only() {
+        // (0)─[2]─(1), (0)─[3]─(1) and the longer (0)─[4]─[5]─(1)
+        let mut network = Network::new();
+        network.add_nodes(2, 4);
+        network.add_edges(vec![
+            (0, 2, 100.0), (2, 1, 100.0), (0, 3, 100.0), (3, 1, 100.0),
+            (0, 4, 100.0), (4, 5, 100.0), (5, 1, 100.0),
+        ]);
+        let ecmp = ECMP::new(&network, 0);
+        let routes = ecmp.candidates(0, 1).iter()
+            .map(|path| network.node_sequence(path))
+            .collect::<Vec<_>>();
+        assert_eq!(routes.len(), 2);
+        assert!(routes.contains(&vec![0, 2, 1]));
+        assert!(routes.contains(&vec![0, 3, 1]));
+        let avbs = (1..=16)
+            .map(|size| AVB::new(0, 1, size * 100, 1000, 1000, 'A'))
+            .collect::<Vec<_>>();
+        let mut flowtable = FlowTable::new();
+        flowtable.append(vec![], avbs.clone());
+        let spread = (0..16)
+            .map(|nth| ecmp.hash(&flowtable, nth) % 2)
+            .collect::<Vec<_>>();
+        // pinned so that the spread stays the same across platforms and compiler versions
+        assert_eq!(spread, [1, 0, 0, 0, 0, 0, 1, 1, 0, 1, 1, 0, 0, 0, 1, 0]);
+        // the same stream takes the same path wherever it sits in the flow table
+        let mut reversed = FlowTable::new();
+        reversed.append(vec![], avbs.into_iter().rev().collect());
+        assert!((0..16).all(|nth| ecmp.hash(&flowtable, nth) == ecmp.hash(&reversed, 15 - nth)));
+    }
+
+    #[test]
+    fn it_skips_disconnected_end_devices() {
+        // (0)─[3]─(1) while (2) is left alone
+        let mut network = Network::new();
+        network.add_nodes(3, 1);
+        network.add_edges(vec![(0, 3, 100.0), (3, 1, 100.0)]);
+        assert!(ECMP::new(&network, 0).candidates(0, 2).is_empty());
+        assert!(STP::new(&network, None).candidates(2, 1).is_empty());
+        assert_eq!(STP::new(&network, None).candidates(0, 1).len(), 1);
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.algorithm = String::from("ecmp");
+        let mut cnc = CNC::new(network, config);
+        let avbs = vec![AVB::new(0, 2, 300, 1000, 1000, 'A')];
+        let added = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cnc.add_streams(vec![], avbs)));
+        assert!(added.is_err());
+    }
+}
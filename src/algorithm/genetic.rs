@@ -0,0 +1,197 @@
+use std::cmp::Ordering;
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+
+use super::base::yens::Yens;
+use super::Algorithm;
+use crate::cnc::Toolbox;
+use crate::component::Solution;
+use crate::network::{Network, Path};
+use crate::MAX_K;
+
+const POPULATION_SIZE: usize = 20;
+const TOURNAMENT_SIZE: usize = 2;
+const CROSSOVER_RATE: f64 = 0.9;
+/// 每一代直接保留的最佳個體數
+const ELITES: usize = 2;
+
+// J. H. Holland, "Adaptation in Natural and Artificial Systems," University of Michigan Press,
+// Ann Arbor, 1975.
+
+/// 交配方式，基因為每條資料流選用的路徑
+/// * `Uniform` - 每個基因各自以一半的機率取自另一個親代
+/// * `Segment` - 隨機取一段連續的基因，整段取自另一個親代
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Crossover {
+    Uniform,
+    Segment,
+}
+
+pub struct GA {
+    yens: Yens,
+    seed: u64,
+    crossover: Crossover,
+}
+
+#[derive(Clone)]
+struct Individual {
+    solution: Solution,
+    cost: f64,
+}
+
+impl Algorithm for GA {
+    fn candidates(&self, src: usize, dst: usize) -> &Vec<Path> {
+        self.yens.k_shortest_paths(src.into(), dst.into())
+    }
+    /// 以上一次的解及其突變作為初始族群，使大多數個體的路徑與上一次相近，減少重新路由
+    fn configure(&mut self, last_run: Solution, deadline: Instant, toolbox: Toolbox) -> Solution {
+        let flowtable = last_run.flowtable();
+        let mut rng = ChaChaRng::seed_from_u64(self.seed);
+        let counts = (0..flowtable.len())
+            .map(|nth| flowtable.ends(nth))
+            .map(|(src, dst)| self.candidates(src, dst).len())
+            .collect::<Vec<_>>();
+        let mutation_rate = 1.0 / counts.len().max(1) as f64;
+        let mutate = |solution: &mut Solution, rng: &mut ChaChaRng| {
+            for (nth, &count) in counts.iter().enumerate() {
+                if rng.gen_bool(mutation_rate) {
+                    solution.select(nth, rng.gen_range(0..count));
+                }
+            }
+        };
+
+        let mut population = Vec::with_capacity(POPULATION_SIZE);
+        let mut solution = last_run.clone();
+        let (cost, stop) = toolbox.evaluate_cost(&mut solution);
+        if stop || counts.is_empty() {
+            return solution;
+        }
+        population.push(Individual { solution, cost });
+        while population.len() < POPULATION_SIZE {
+            // 時間到時以目前的族群作答，此時不會再進入下面的世代
+            if Instant::now() > deadline {
+                break;
+            }
+            let mut solution = last_run.clone();
+            mutate(&mut solution, &mut rng);
+            let (cost, stop) = toolbox.evaluate_cost(&mut solution);
+            if stop {
+                return solution;
+            }
+            population.push(Individual { solution, cost });
+        }
+
+        #[allow(unused_variables)]
+        let mut generation = 0;
+        'outer: while Instant::now() < deadline {
+            generation += 1;
+            population.sort_by(compare_individual);
+            let mut offsprings = population[..ELITES].to_vec();
+            while offsprings.len() < POPULATION_SIZE {
+                if Instant::now() > deadline {
+                    // 已評估的子代可能比族群中的任何個體都好，一併納入最後的比較
+                    population.extend(offsprings.drain(ELITES..));
+                    break 'outer;
+                }
+                let father = tournament(&population, &mut rng);
+                let mother = tournament(&population, &mut rng);
+                let mut child = father.solution.clone();
+                if rng.gen_bool(CROSSOVER_RATE) {
+                    for nth in self.genes(counts.len(), &mut rng) {
+                        child.select(nth, mother.solution.selection(nth).next().unwrap());
+                    }
+                }
+                mutate(&mut child, &mut rng);
+                let (cost, stop) = toolbox.evaluate_cost(&mut child);
+                if stop {
+                    return child;
+                }
+                offsprings.push(Individual { solution: child, cost });
+            }
+            population = offsprings;
+        }
+
+        #[cfg(debug_assertions)]
+        println!("GA generation = {}", generation);
+
+        population.into_iter()
+            .min_by(compare_individual)
+            .map(|individual| individual.solution)
+            .unwrap()
+    }
+}
+
+impl GA {
+    pub fn new(network: &Network, seed: u64, crossover: Crossover) -> Self {
+        let mut yens = Yens::new(network, MAX_K);
+        yens.compute(network);
+        GA { yens, seed, crossover }
+    }
+    /// 交配時要從另一個親代取得的基因（資料流編號）
+    fn genes(&self, len: usize, rng: &mut ChaChaRng) -> Vec<usize> {
+        match self.crossover {
+            Crossover::Uniform => (0..len).filter(|_| rng.gen_bool(0.5)).collect(),
+            Crossover::Segment => {
+                let start = rng.gen_range(0..len);
+                let end = rng.gen_range(start..len) + 1;
+                (start..end).collect()
+            }
+        }
+    }
+}
+
+fn compare_individual(x: &Individual, y: &Individual) -> Ordering {
+    x.cost.partial_cmp(&y.cost).unwrap_or(Ordering::Equal)
+}
+
+fn tournament<'a>(population: &'a [Individual], rng: &mut ChaChaRng) -> &'a Individual {
+    (0..TOURNAMENT_SIZE)
+        .map(|_| &population[rng.gen_range(0..population.len())])
+        .min_by(|x, y| compare_individual(x, y))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnc::CNC;
+    use crate::utils::stream::AVB;
+    use crate::utils::yaml;
+
+    #[test]
+    fn it_crosses_over_genes_within_range() {
+        let network = Network::new();
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        let ga = GA::new(&network, 0, Crossover::Segment);
+        for _ in 0..100 {
+            let genes = ga.genes(10, &mut rng);
+            assert!(!genes.is_empty());
+            assert!(*genes.last().unwrap() < 10);
+            assert!(genes.windows(2).all(|pair| pair[0] + 1 == pair[1]));
+        }
+        let ga = GA::new(&network, 0, Crossover::Uniform);
+        let genes = ga.genes(1000, &mut rng);
+        assert!(genes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(genes.len() > 400 && genes.len() < 600);
+    }
+
+    #[test]
+    fn it_stops_seeding_at_deadline() {
+        // (0)─[2]─(1) and (0)─[3]─(1)
+        let mut network = Network::new();
+        network.add_nodes(2, 2);
+        network.add_edges(vec![(0, 2, 100.0), (2, 1, 100.0), (0, 3, 100.0), (3, 1, 100.0)]);
+        let avbs = vec![AVB::new(0, 1, 300, 1000, 1000, 'A'); 4];
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.algorithm = String::from("ga");
+        config.early_stop = false;
+        config.timeout = 0;
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(vec![], avbs);
+        cnc.configure();
+        // no mutant is evaluated after the deadline, so every stream stays on its first route
+        assert!((0..4).all(|nth| cnc.solution.selection(nth).current() == Some(0)));
+    }
+}
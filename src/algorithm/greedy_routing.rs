@@ -0,0 +1,234 @@
+use std::time::Instant;
+
+use super::base::yens::Yens;
+use super::Algorithm;
+use crate::cnc::Toolbox;
+use crate::component::{FlowTable, Solution};
+use crate::network::{EdgeIndex, Network, Path};
+use crate::MAX_K;
+
+/// 新資料流依序繞送的順序
+/// * `Index` - 依資料流編號
+/// * `Deadline` - deadline 越小者越先
+/// * `Utilization` - 平均速率越大者越先
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sequence {
+    Index,
+    Deadline,
+    Utilization,
+}
+
+/// 選擇候選路徑的準則
+/// * `Load` - 路徑上最壅塞的連線負載最小者，平手時取總負載較小者
+/// * `WCD` - AVB 資料流選擇使自己及已繞送的 AVB 資料流最差延遲總和增加最少者；
+///   TSN 資料流的影響須排程後才能得知，仍以負載選擇
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Criterion {
+    Load,
+    WCD,
+}
+
+/// 不隨機、不迭代的貪婪繞送，上一次的資料流維持原路徑，新資料流逐一選擇目前最不壅塞的路徑
+pub struct CGR {
+    yens: Yens,
+    sequence: Sequence,
+    criterion: Criterion,
+}
+
+/// 一條新資料流對頻寬的需求
+struct Demand {
+    nth: usize,
+    size: u32,
+    period: u32,
+    deadline: u32,
+    is_avb: bool,
+}
+
+impl Algorithm for CGR {
+    fn candidates(&self, src: usize, dst: usize) -> &Vec<Path> {
+        self.yens.k_shortest_paths(src.into(), dst.into())
+    }
+    fn configure(&mut self, last_run: Solution, _deadline: Instant, toolbox: Toolbox) -> Solution {
+        let flowtable = last_run.flowtable();
+        let network = last_run.network();
+        let mut solution = last_run;
+
+        // 已繞送的 AVB 資料流只記在試算用的副本上，實際的 `traversed_avbs` 交由排程器在允入後更新
+        let mut probe = solution.clone();
+        let mut loads = vec![0.0; network.edge_count()];
+        for nth in flowtable.backgrounds() {
+            let demand = demand_of(nth, &flowtable);
+            let kth = solution.selection(nth).next().unwrap();
+            for &edge in flowtable.candidate(nth, kth) {
+                loads[edge.index()] += demand.load_on(edge, &network);
+            }
+        }
+
+        for demand in self.sequence(&flowtable) {
+            let nth = demand.nth;
+            let count = flowtable.count_candidates(nth);
+            let kth = match demand.is_avb && self.criterion == Criterion::WCD {
+                true  => (0..count)
+                    .map(|kth| (increase_wcd(nth, kth, &mut probe, &toolbox), kth))
+                    .min_by(|x, y| x.0.partial_cmp(&y.0).unwrap())
+                    .map(|(_, kth)| kth)
+                    .unwrap(),
+                false => (0..count)
+                    .map(|kth| (congest(&demand, flowtable.candidate(nth, kth), &loads, &network), kth))
+                    .min_by(|x, y| x.0.partial_cmp(&y.0).unwrap())
+                    .map(|(_, kth)| kth)
+                    .unwrap(),
+            };
+            solution.select(nth, kth);
+            probe.select(nth, kth);
+            for &edge in flowtable.candidate(nth, kth) {
+                loads[edge.index()] += demand.load_on(edge, &network);
+                if demand.is_avb {
+                    probe.traversed_avbs.insert(edge, nth);
+                }
+            }
+        }
+
+        toolbox.evaluate_cost(&mut solution);
+        solution
+    }
+}
+
+impl CGR {
+    pub fn new(network: &Network, sequence: Sequence, criterion: Criterion) -> Self {
+        let mut yens = Yens::new(network, MAX_K);
+        yens.compute(network);
+        CGR { yens, sequence, criterion }
+    }
+    /// 依設定的順序列出新資料流，順序相同者依編號
+    fn sequence(&self, flowtable: &FlowTable) -> Vec<Demand> {
+        let mut demands = flowtable.inputs()
+            .map(|nth| demand_of(nth, flowtable))
+            .collect::<Vec<_>>();
+        match self.sequence {
+            Sequence::Index       => (),
+            Sequence::Deadline    => demands.sort_by_key(|demand| demand.deadline),
+            Sequence::Utilization => demands.sort_by(|x, y| {
+                let utilization = |demand: &Demand| demand.size as f64 / demand.period as f64;
+                utilization(y).partial_cmp(&utilization(x)).unwrap()
+            }),
+        }
+        demands
+    }
+}
+
+impl Demand {
+    fn load_on(&self, edge: EdgeIndex, network: &Network) -> f64 {
+        network.duration_on(edge, self.size) / self.period as f64
+    }
+}
+
+fn demand_of(nth: usize, flowtable: &FlowTable) -> Demand {
+    match flowtable.tsns().contains(&nth) {
+        true  => {
+            let spec = flowtable.tsn_spec(nth);
+            Demand { nth, size: spec.size, period: spec.period, deadline: spec.deadline, is_avb: false }
+        }
+        false => {
+            let spec = flowtable.avb_spec(nth);
+            Demand { nth, size: spec.size, period: spec.period, deadline: spec.deadline, is_avb: true }
+        }
+    }
+}
+
+/// 繞送後路徑上最壅塞的連線負載，及路徑上的總負載
+fn congest(demand: &Demand, route: &[EdgeIndex], loads: &[f64], network: &Network) -> (f64, f64) {
+    let mut bottleneck = 0.0;
+    let mut total = 0.0;
+    for &edge in route {
+        let load = loads[edge.index()] + demand.load_on(edge, network);
+        bottleneck = f64::max(bottleneck, load);
+        total += load;
+    }
+    (bottleneck, total)
+}
+
+/// AVB 資料流選用第 `kth` 條路徑時，自己及路徑上已繞送的 AVB 資料流之最差延遲總和增加多少
+fn increase_wcd(avb: usize, kth: usize, solution: &mut Solution, toolbox: &Toolbox) -> f64 {
+    let flowtable = solution.flowtable();
+    let route = flowtable.candidate(avb, kth);
+    let mut affected = route.iter()
+        .flat_map(|&edge| solution.traversed_avbs.iter(edge))
+        .collect::<Vec<_>>();
+    affected.sort_unstable();
+    affected.dedup();
+    let sum_wcds = |solution: &Solution| -> f64 {
+        let mut sum = 0.0;
+        for &other in &affected {
+            let kth = solution.selection(other).next().unwrap();
+            sum += toolbox.evaluate_wcd(other, kth, solution) as f64;
+        }
+        sum
+    };
+
+    let before = sum_wcds(solution);
+    for &edge in route {
+        solution.traversed_avbs.insert(edge, avb);
+        solution.mark_dirty(edge);
+    }
+    let after = sum_wcds(solution) + toolbox.evaluate_wcd(avb, kth, solution) as f64;
+    for &edge in route {
+        solution.traversed_avbs.remove(edge, avb);
+        solution.mark_dirty(edge);
+    }
+    after - before
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cnc::CNC;
+    use crate::network::Network;
+    use crate::utils::stream::{AVB, TSN};
+    use crate::utils::yaml;
+
+    #[test]
+    fn it_spreads_streams_over_diamond() {
+        // (0)─[2]─(1) and (0)─[3]─(1)
+        let mut network = Network::new();
+        network.add_nodes(2, 2);
+        network.add_edges(vec![(0, 2, 100.0), (2, 1, 100.0), (0, 3, 100.0), (3, 1, 100.0)]);
+        let tsns = vec![TSN::new(0, 1, 100, 1000, 1000, 0)];
+        let avbs = vec![
+            AVB::new(0, 1, 300, 1000, 1000, 'A'),
+            AVB::new(0, 1, 300, 1000, 1000, 'A'),
+        ];
+        for &criterion in &["load", "wcd"] {
+            let mut config = yaml::load_config("data/config/default.yaml");
+            config.algorithm = String::from("cgr");
+            config.greedy_criterion = String::from(criterion);
+            let mut cnc = CNC::new(network.clone(), config);
+            cnc.add_streams(tsns.clone(), avbs.clone());
+            cnc.configure();
+            let route = |nth: usize| cnc.solution.selection(nth).current().unwrap();
+            assert_ne!(route(1), route(2));
+            if criterion == "load" {
+                // the second AVB prefers the lighter TSN over the heavier AVB
+                assert_eq!(route(0), route(2));
+            }
+        }
+    }
+
+    #[test]
+    fn it_admits_avb_just_under_share() {
+        let mut network = Network::new();
+        network.add_nodes(2, 0);
+        network.add_edges(vec![(0, 1, 100.0)]);
+        // 74 bytes/μs of the 75 bytes/μs reserved for class A
+        let avbs = vec![AVB::new(0, 1, 740, 10, 10000, 'A')];
+        for &criterion in &["load", "wcd"] {
+            let mut config = yaml::load_config("data/config/default.yaml");
+            config.algorithm = String::from("cgr");
+            config.greedy_criterion = String::from(criterion);
+            let mut cnc = CNC::new(network.clone(), config);
+            cnc.add_streams(vec![], avbs.clone());
+            cnc.configure();
+            assert!(cnc.solution.outcome(0).is_schedulable());
+            assert_eq!(cnc.solution.traversed_avbs.iter(0.into()).collect::<Vec<_>>(), [0]);
+        }
+    }
+}
@@ -1,11 +1,27 @@
 mod adams_ants;
 mod base;
+mod branch_bound;
+mod equal_cost;
+mod genetic;
+mod greedy_routing;
+mod nondominated_genetic;
 mod routing_optimism;
 mod shortest_path;
+mod simulated_annealing;
+mod spanning_tree;
+mod tabu_search;
 
 pub use adams_ants::ACO;
-pub use routing_optimism::RO;
+pub use branch_bound::BNB;
+pub use equal_cost::ECMP;
+pub use genetic::{Crossover, GA};
+pub use greedy_routing::{Criterion, Sequence, CGR};
+pub use nondominated_genetic::NSGA;
+pub use routing_optimism::{Scope, RO};
 pub use shortest_path::SPF;
+pub use simulated_annealing::{Cooling, SA};
+pub use spanning_tree::STP;
+pub use tabu_search::TS;
 
 use std::time::Instant;
 use enum_dispatch::enum_dispatch;
@@ -14,7 +30,7 @@ use crate::component::Solution;
 use crate::network::Path;
 
 #[enum_dispatch]
-pub enum AlgorithmEnum { ACO, RO, SPF }
+pub enum AlgorithmEnum { ACO, BNB, CGR, ECMP, GA, NSGA, RO, SA, SPF, STP, TS }
 
 #[enum_dispatch(AlgorithmEnum)]
 pub trait Algorithm {
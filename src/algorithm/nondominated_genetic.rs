@@ -0,0 +1,262 @@
+use std::cmp::Ordering;
+use std::time::Instant;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+
+use super::base::yens::Yens;
+use super::Algorithm;
+use crate::cnc::Toolbox;
+use crate::component::{Objectives, Solution};
+use crate::network::{Network, Path};
+use crate::MAX_K;
+
+const POPULATION_SIZE: usize = 20;
+const CROSSOVER_RATE: f64 = 0.9;
+
+// K. Deb, A. Pratap, S. Agarwal and T. Meyarivan, "A fast and elitist multiobjective genetic
+// algorithm: NSGA-II," in IEEE Transactions on Evolutionary Computation, vol. 6, no. 2,
+// pp. 182-197, April 2002, doi: 10.1109/4235.996017.
+
+pub struct NSGA {
+    yens: Yens,
+    seed: u64,
+    front: Vec<(Objectives, Vec<usize>)>,
+}
+
+struct Individual {
+    solution: Solution,
+    objs: Objectives,
+    costs: Vec<f64>,
+    rank: usize,
+    crowding: f64,
+}
+
+impl Algorithm for NSGA {
+    fn candidates(&self, src: usize, dst: usize) -> &Vec<Path> {
+        self.yens.k_shortest_paths(src.into(), dst.into())
+    }
+    /// 以 NSGA-II 搜尋路徑選擇的 Pareto front，並回傳其中加權成本最低者
+    fn configure(&mut self, last_run: Solution, deadline: Instant, toolbox: Toolbox) -> Solution {
+        let flowtable = last_run.flowtable();
+        let mut rng = ChaChaRng::seed_from_u64(self.seed);
+        let counts = (0..flowtable.len())
+            .map(|nth| flowtable.ends(nth))
+            .map(|(src, dst)| self.candidates(src, dst).len())
+            .collect::<Vec<_>>();
+        let mutation_rate = 1.0 / counts.len().max(1) as f64;
+
+        let evaluate = |mut solution: Solution| {
+            let objs = toolbox.evaluate_objectives(&mut solution);
+            let costs = toolbox.objectives().iter()
+                .map(|&obj| objs.get(obj))
+                .collect();
+            Individual { solution, objs, costs, rank: 0, crowding: 0.0 }
+        };
+
+        let mut population = vec![evaluate(last_run.clone())];
+        while population.len() < POPULATION_SIZE {
+            let mut solution = last_run.clone();
+            for (nth, &count) in counts.iter().enumerate() {
+                solution.select(nth, rng.gen_range(0..count));
+            }
+            population.push(evaluate(solution));
+        }
+        assign_rank_and_crowding(&mut population);
+
+        while Instant::now() < deadline {
+            let mut offsprings = Vec::with_capacity(POPULATION_SIZE);
+            while offsprings.len() < POPULATION_SIZE {
+                let father = tournament(&population, &mut rng);
+                let mother = tournament(&population, &mut rng);
+                let mut child = father.solution.clone();
+                let crossover = rng.gen_bool(CROSSOVER_RATE);
+                for (nth, &count) in counts.iter().enumerate() {
+                    if crossover && rng.gen_bool(0.5) {
+                        child.select(nth, mother.solution.selection(nth).next().unwrap());
+                    }
+                    if rng.gen_bool(mutation_rate) {
+                        child.select(nth, rng.gen_range(0..count));
+                    }
+                }
+                offsprings.push(evaluate(child));
+            }
+            population.append(&mut offsprings);
+            assign_rank_and_crowding(&mut population);
+            population.sort_by(compare_individual);
+            population.truncate(POPULATION_SIZE);
+        }
+
+        let mut front = population.into_iter()
+            .filter(|individual| individual.rank == 0)
+            .collect::<Vec<_>>();
+        front.sort_by(|x, y| x.costs.partial_cmp(&y.costs).unwrap_or(Ordering::Equal));
+        front.dedup_by(|x, y| x.costs == y.costs);
+        self.front = front.iter()
+            .map(|individual| {
+                let selections = (0..counts.len())
+                    .map(|nth| individual.solution.selection(nth).next().unwrap())
+                    .collect();
+                (individual.objs.clone(), selections)
+            })
+            .collect();
+
+        front.into_iter()
+            .map(|mut individual| {
+                let (cost, _stop) = toolbox.evaluate_cost(&mut individual.solution);
+                (cost, individual.solution)
+            })
+            .min_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(Ordering::Equal))
+            .map(|(_, solution)| solution)
+            .unwrap()
+    }
+}
+
+impl NSGA {
+    pub fn new(network: &Network, seed: u64) -> Self {
+        let mut yens = Yens::new(network, MAX_K);
+        yens.compute(network);
+        NSGA { yens, seed, front: vec![] }
+    }
+    /// 上一次配置得到的 Pareto front，每一項為目標值及每條資料流選用的路徑
+    pub fn front(&self) -> &Vec<(Objectives, Vec<usize>)> {
+        &self.front
+    }
+}
+
+fn dominates(x: &[f64], y: &[f64]) -> bool {
+    x.iter().zip(y).all(|(a, b)| a <= b) && x.iter().zip(y).any(|(a, b)| a < b)
+}
+
+/// 依支配關係分層（fast non-dominated sort），並計算每層中的擁擠距離
+fn assign_rank_and_crowding(population: &mut [Individual]) {
+    let len = population.len();
+    let mut dominated_by = vec![0; len];
+    let mut dominating = vec![vec![]; len];
+    for i in 0..len {
+        for j in 0..len {
+            if dominates(&population[i].costs, &population[j].costs) {
+                dominating[i].push(j);
+            } else if dominates(&population[j].costs, &population[i].costs) {
+                dominated_by[i] += 1;
+            }
+        }
+    }
+    let mut rank = 0;
+    let mut front = (0..len).filter(|&i| dominated_by[i] == 0).collect::<Vec<_>>();
+    while !front.is_empty() {
+        let mut next = vec![];
+        for &i in &front {
+            population[i].rank = rank;
+            for &j in &dominating[i] {
+                dominated_by[j] -= 1;
+                if dominated_by[j] == 0 { next.push(j); }
+            }
+        }
+        assign_crowding(population, &front);
+        front = next;
+        rank += 1;
+    }
+}
+
+fn assign_crowding(population: &mut [Individual], front: &[usize]) {
+    let dimension = population[front[0]].costs.len();
+    front.iter().for_each(|&i| population[i].crowding = 0.0);
+    let mut front = front.to_vec();
+    for m in 0..dimension {
+        front.sort_by(|&i, &j| population[i].costs[m]
+            .partial_cmp(&population[j].costs[m]).unwrap_or(Ordering::Equal));
+        let first = population[front[0]].costs[m];
+        let last = population[front[front.len() - 1]].costs[m];
+        population[front[0]].crowding = f64::INFINITY;
+        population[front[front.len() - 1]].crowding = f64::INFINITY;
+        if last == first { continue; }
+        for w in 1..front.len().saturating_sub(1) {
+            let prev = population[front[w - 1]].costs[m];
+            let next = population[front[w + 1]].costs[m];
+            population[front[w]].crowding += (next - prev) / (last - first);
+        }
+    }
+}
+
+/// 層數低者優先，同層時擁擠距離大者優先
+fn compare_individual(x: &Individual, y: &Individual) -> Ordering {
+    x.rank.cmp(&y.rank)
+        .then(y.crowding.partial_cmp(&x.crowding).unwrap_or(Ordering::Equal))
+}
+
+fn tournament<'a>(population: &'a [Individual], rng: &mut ChaChaRng) -> &'a Individual {
+    let x = population.choose(rng).unwrap();
+    let y = population.choose(rng).unwrap();
+    match compare_individual(x, y) {
+        Ordering::Greater => y,
+        _                 => x,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::AlgorithmEnum;
+    use crate::cnc::CNC;
+    use crate::utils::stream::AVB;
+    use crate::utils::yaml;
+
+    #[test]
+    fn it_sorts_by_dominance() {
+        let network = Network::new();
+        let individual = |costs: Vec<f64>| Individual {
+            solution: Solution::new(&network),
+            objs: Objectives::new(),
+            costs, rank: 0, crowding: 0.0,
+        };
+        let mut population = vec![
+            individual(vec![1.0, 5.0]),
+            individual(vec![2.0, 2.0]),
+            individual(vec![3.0, 3.0]),
+            individual(vec![5.0, 1.0]),
+            individual(vec![4.0, 4.0]),
+        ];
+        assign_rank_and_crowding(&mut population);
+        let ranks = population.iter().map(|x| x.rank).collect::<Vec<_>>();
+        assert_eq!(ranks, [0, 0, 1, 0, 2]);
+        assert_eq!(population[0].crowding, f64::INFINITY);
+        assert_eq!(population[1].crowding, 2.0);
+        assert!(dominates(&[1.0, 2.0], &[1.0, 3.0]));
+        assert!(!dominates(&[1.0, 2.0], &[1.0, 2.0]));
+    }
+
+    #[test]
+    fn it_exports_nondominated_front() {
+        // (0)─[2]─(1) and (0)─[3]─(1)
+        let mut network = Network::new();
+        network.add_nodes(2, 2);
+        network.add_edges(vec![(0, 2, 100.0), (2, 1, 100.0), (0, 3, 100.0), (3, 1, 100.0)]);
+        let avbs = vec![
+            AVB::new(0, 1, 300, 1000, 1000, 'A'),
+            AVB::new(0, 1, 300, 1000, 1000, 'A'),
+            AVB::new(1, 0, 300, 1000, 1000, 'A'),
+        ];
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.algorithm = String::from("nsga2");
+        config.timeout = 20000;
+        config.objectives.insert(String::from("max_link_utilization"), 1.0);
+        config.pareto_front = Some(String::from("/dev/null"));
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(vec![], avbs);
+        cnc.configure();
+        let front = match &cnc.algorithm {
+            AlgorithmEnum::NSGA(nsga) => nsga.front(),
+            _                         => unreachable!(),
+        };
+        let costs = front.iter()
+            .map(|(objs, _)| cnc.evaluator.objectives().iter().map(|&obj| objs.get(obj)).collect())
+            .collect::<Vec<Vec<f64>>>();
+        assert!(!costs.is_empty());
+        for x in &costs {
+            assert!(costs.iter().all(|y| !dominates(y, x)));
+        }
+        assert!(front.iter().all(|(_, selections)| selections.len() == 3));
+    }
+}
@@ -1,21 +1,31 @@
 use std::time::Instant;
 
-use rand::seq::SliceRandom;
+use rand::seq::{IteratorRandom, SliceRandom};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 
 use super::base::yens::Yens;
 use super::Algorithm;
 use crate::cnc::Toolbox;
-use crate::component::Solution;
+use crate::component::{FlowTable, Solution};
 use crate::network::{Network, Path};
 use crate::MAX_K;
 
 const ALPHA_PORTION: f64 = 0.5;
 
+/// 要重新繞送的資料流
+/// * `AVB` - 如同原論文，只重新繞送 AVB 資料流，TSN 資料流維持原本的路徑
+/// * `Joint` - 一併重新繞送 TSN 資料流，但不接受讓更多 TSN 資料流無法排程的解
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    AVB,
+    Joint,
+}
+
 pub struct RO {
     yens: Yens,
     seed: u64,
+    scope: Scope,
 }
 
 impl Algorithm for RO {
@@ -29,30 +39,39 @@ impl Algorithm for RO {
         let mut epoch = 0;
         let mut rng = ChaChaRng::seed_from_u64(self.seed);
 
+        let streams = self.streams(&flowtable);
         let mut global_best = last_run;
         let (mut global_best_cost, _stop) = toolbox.evaluate_cost(&mut global_best);
+        let mut global_best_failures = self.count_failures(&global_best, &toolbox);
 
         'outer: while Instant::now() < deadline {
             epoch += 1;
             let mut neighbor = global_best.clone();
 
             // PHASE 1: randomized greedy algorithm
-            for &nth in flowtable.avbs() {
+            for &nth in streams.iter() {
                 let (src, dst) = flowtable.ends(nth);
-                let candidate_count = self.candidates(src, dst).len();
+                let candidates = self.candidates(src, dst);
+                let candidate_count = candidates.len();
                 // XXX (candidate_cnt as f64 * ALPHA_PORTION).ceil() outperforms
                 let alpha = (candidate_count as f64 * ALPHA_PORTION) as usize;
                 // XXX (0..candidate_cnt).choose_multiple outperforms
                 let set = choose_n_within_k(alpha, candidate_count, &mut rng);
-                let kth = set.into_iter()
-                    .min_by_key(|&kth| toolbox.evaluate_wcd(nth, kth, &global_best))
-                    .unwrap_or(0);
-                neighbor.select(nth, kth);
+                // TSN 資料流排程之前無從得知延遲，改以跳數貪婪選擇
+                let kth = match flowtable.is_avb(nth) {
+                    true  => set.into_iter()
+                                .min_by_key(|&kth| toolbox.evaluate_wcd(nth, kth, &global_best)),
+                    false => set.into_iter()
+                                .min_by_key(|&kth| candidates[kth].len()),
+                };
+                neighbor.select(nth, kth.unwrap_or(0));
             }
             let (cost, stop) = toolbox.evaluate_cost(&mut neighbor);
-            if cost < global_best_cost {
+            let accepted = self.accept(&neighbor, cost, (global_best_cost, global_best_failures), &toolbox);
+            if let Some(failures) = accepted {
                 // XXX global_best = neighbor.clone() outperforms
                 global_best_cost = cost;
+                global_best_failures = failures;
             }
             #[cfg(debug_assertions)]
             println!("start iteration #{}", epoch);
@@ -71,13 +90,20 @@ impl Algorithm for RO {
                     break 'outer; // 找到可行解，返回
                 }
 
-                let nth = flowtable.avbs().choose(&mut rng).cloned().unwrap();
+                let nth = streams.choose(&mut rng).cloned().unwrap();
                 let old_kth = global_best.selection(nth).current().unwrap();
                 let (src, dst) = flowtable.ends(nth);
                 let candidate_count = self.candidates(src, dst).len();
-                let kth = (0..candidate_count)
-                    .min_by_key(|&kth| toolbox.evaluate_wcd(nth, kth, &global_best))
-                    .unwrap_or(0);
+                // TSN 資料流沒有類似最差延遲的指標，隨機換一條路徑
+                let kth = match flowtable.is_avb(nth) {
+                    true  => (0..candidate_count)
+                                .min_by_key(|&kth| toolbox.evaluate_wcd(nth, kth, &global_best))
+                                .unwrap_or(0),
+                    false => (0..candidate_count)
+                                .filter(|&kth| kth != old_kth)
+                                .choose(&mut rng)
+                                .unwrap_or(old_kth),
+                };
 
                 if old_kth == kth {
                     continue;
@@ -91,9 +117,11 @@ impl Algorithm for RO {
                     break 'outer;
                 }
 
-                if cost < global_best_cost {
+                let accepted = self.accept(&neighbor, cost, (global_best_cost, global_best_failures), &toolbox);
+                if let Some(failures) = accepted {
                     global_best = neighbor.clone();
                     global_best_cost = cost;
+                    global_best_failures = failures;
                     beta = 0;
                 } else {
                     // 恢復上一動
@@ -110,10 +138,37 @@ impl Algorithm for RO {
 }
 
 impl RO {
-    pub fn new(network: &Network, seed: u64) -> Self {
+    pub fn new(network: &Network, seed: u64, scope: Scope) -> Self {
         let mut yens = Yens::new(&network, MAX_K);
         yens.compute(&network);
-        RO { yens, seed }
+        RO { yens, seed, scope }
+    }
+    fn streams(&self, flowtable: &FlowTable) -> Vec<usize> {
+        match self.scope {
+            Scope::AVB   => flowtable.avbs().clone(),
+            Scope::Joint => flowtable.tsns().iter()
+                .chain(flowtable.avbs())
+                .cloned()
+                .collect(),
+        }
+    }
+    /// 只重新繞送 AVB 資料流時不影響 TSN 資料流的排程，不需作為限制
+    fn count_failures(&self, solution: &Solution, toolbox: &Toolbox) -> usize {
+        match self.scope {
+            Scope::AVB   => 0,
+            Scope::Joint => toolbox.count_tsn_failures(solution),
+        }
+    }
+    /// 成本較低且無法排程的 TSN 資料流沒有變多時接受鄰居，並回傳其 TSN 失敗數
+    ///
+    /// 成本沒有降低時直接拒絕，不必逐一檢查 TSN 資料流。
+    fn accept(&self, neighbor: &Solution, cost: f64, (best_cost, best_failures): (f64, usize),
+              toolbox: &Toolbox) -> Option<usize> {
+        if cost >= best_cost {
+            return None;
+        }
+        let failures = self.count_failures(neighbor, toolbox);
+        (failures <= best_failures).then_some(failures)
     }
 }
 
@@ -127,3 +182,74 @@ fn choose_n_within_k(n: usize, k: usize, rng: &mut ChaChaRng) -> Vec<usize> {
     vec.sort_unstable();
     vec.into_iter().map(|(_, i)| i).take(n).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnc::CNC;
+    use crate::utils::stream::{Shaper, AVB, TSN};
+    use crate::utils::yaml;
+
+    /// (0)─[2]─(1) and (0)─[3]─(1), carrying two ATS-shaped TSN streams that miss their deadline
+    /// when they share a route: (15 + 3) * 2 = 36 alone but (15 + 6) * 2 = 42 together
+    fn setup(scope: &str) -> CNC {
+        let mut network = Network::new();
+        network.add_nodes(2, 2);
+        network.add_edges(vec![(0, 2, 100.0), (2, 1, 100.0), (0, 3, 100.0), (3, 1, 100.0)]);
+        let tsns = vec![TSN { shaper: Shaper::ATS, ..TSN::new(0, 1, 300, 1000, 40, 0) }; 2];
+        let avbs = vec![AVB::new(0, 1, 300, 1000, 1000, 'A')];
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.algorithm = String::from("ro");
+        config.ro_scope = String::from(scope);
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(tsns, avbs);
+        cnc
+    }
+
+    #[test]
+    fn it_reroutes_tsns_only_when_joint() {
+        let network = yaml::load_network("data/network/typical.yaml");
+        let config = yaml::load_config("data/config/default.yaml");
+        let (tsns, avbs) = yaml::load_streams("data/streams/motiv-light.yaml", 1);
+        let ro = |scope| RO::new(&network, 0, scope);
+        let mut cnc = CNC::new(network.clone(), config);
+        cnc.add_streams(tsns, avbs);
+        let flowtable = &cnc.flowtable;
+        assert_eq!(&ro(Scope::AVB).streams(flowtable), flowtable.avbs());
+        let joint = ro(Scope::Joint).streams(flowtable);
+        assert_eq!(joint.len(), flowtable.len());
+        assert!(flowtable.tsns().iter().all(|tsn| joint.contains(tsn)));
+    }
+
+    #[test]
+    fn it_reroutes_a_tsn_to_schedule_it() {
+        let configure = |scope| {
+            let mut cnc = setup(scope);
+            cnc.configure();
+            let toolbox = Toolbox::pack(&cnc.scheduler, &cnc.evaluator, &cnc.solution, &cnc.config);
+            let routes = (0..2)
+                .map(|tsn| cnc.solution.selection(tsn).current().unwrap())
+                .collect::<Vec<_>>();
+            (routes, toolbox.count_tsn_failures(&cnc.solution))
+        };
+        assert_eq!(configure("avb"), (vec![0, 0], 2));
+        let (routes, failures) = configure("joint");
+        assert_ne!(routes[0], routes[1]);
+        assert_eq!(failures, 0);
+    }
+
+    #[test]
+    fn it_refuses_a_cheaper_neighbor_with_more_tsn_failures() {
+        let cnc = setup("joint");
+        let toolbox = Toolbox::pack(&cnc.scheduler, &cnc.evaluator, &cnc.solution, &cnc.config);
+        let mut neighbor = cnc.solution.clone();
+        let (cost, _stop) = toolbox.evaluate_cost(&mut neighbor);
+        assert_eq!(toolbox.count_tsn_failures(&neighbor), 2);
+        let joint = RO::new(&cnc.network, 0, Scope::Joint);
+        assert_eq!(joint.accept(&neighbor, cost, (cost + 1.0, 1), &toolbox), None);
+        assert_eq!(joint.accept(&neighbor, cost, (cost + 1.0, 2), &toolbox), Some(2));
+        assert_eq!(joint.accept(&neighbor, cost, (cost, 2), &toolbox), None);
+        let avb = RO::new(&cnc.network, 0, Scope::AVB);
+        assert_eq!(avb.accept(&neighbor, cost, (cost + 1.0, 0), &toolbox), Some(0));
+    }
+}
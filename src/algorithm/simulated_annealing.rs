@@ -0,0 +1,170 @@
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+
+use super::base::yens::Yens;
+use super::Algorithm;
+use crate::cnc::Toolbox;
+use crate::component::Solution;
+use crate::network::{Network, Path};
+use crate::MAX_K;
+
+/// 每個鄰居最多同時更換幾條資料流的路徑
+const MAX_MOVES: usize = 3;
+/// 溫度低於此值時只接受更好的解
+const MIN_TEMPERATURE: f64 = 1e-9;
+
+// S. Kirkpatrick, C. D. Gelatt and M. P. Vecchi, "Optimization by Simulated Annealing," Science,
+// vol. 220, no. 4598, pp. 671-680, 1983, doi: 10.1126/science.220.4598.671.
+
+/// 降溫方式，`k` 為第幾次迭代
+/// * `Geometric` - 每次迭代乘上固定的比例 `T0 * rate^k`
+/// * `Linear` - 依經過的時間佔 timeout 的比例，線性降到零
+/// * `Logarithmic` - 降溫極慢的 `T0 * ln 2 / ln(k + 2)`，理論上能收斂到全域最佳解
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cooling {
+    Geometric(f64),
+    Linear,
+    Logarithmic,
+}
+
+pub struct SA {
+    yens: Yens,
+    seed: u64,
+    cooling: Cooling,
+    temperature: f64,
+}
+
+impl Algorithm for SA {
+    fn candidates(&self, src: usize, dst: usize) -> &Vec<Path> {
+        self.yens.k_shortest_paths(src.into(), dst.into())
+    }
+    /// 每次迭代隨機更換一至數條資料流的路徑，較差的鄰居依當下溫度以一定機率接受
+    fn configure(&mut self, last_run: Solution, deadline: Instant, toolbox: Toolbox) -> Solution {
+        let flowtable = last_run.flowtable();
+        let mut rng = ChaChaRng::seed_from_u64(self.seed);
+        let counts = (0..flowtable.len())
+            .map(|nth| flowtable.ends(nth))
+            .map(|(src, dst)| self.candidates(src, dst).len())
+            .collect::<Vec<_>>();
+        let movables = (0..counts.len())
+            .filter(|&nth| counts[nth] > 1)
+            .collect::<Vec<_>>();
+
+        let mut current = last_run;
+        let (mut current_cost, stop) = toolbox.evaluate_cost(&mut current);
+        let mut global_best = current.clone();
+        let mut global_best_cost = current_cost;
+        if stop || movables.is_empty() {
+            return global_best;
+        }
+
+        let start = Instant::now();
+        let mut iteration = 0;
+        while Instant::now() < deadline {
+            let temperature = self.temperature_at(iteration, start, deadline);
+            iteration += 1;
+
+            let mut neighbor = current.clone();
+            for _ in 0..rng.gen_range(1..=MAX_MOVES) {
+                let nth = movables[rng.gen_range(0..movables.len())];
+                let kth = neighbor.selection(nth).next().unwrap();
+                // 從其它候選路徑中挑一條
+                let shift = rng.gen_range(1..counts[nth]);
+                neighbor.select(nth, (kth + shift) % counts[nth]);
+            }
+
+            let (cost, stop) = toolbox.evaluate_cost(&mut neighbor);
+            if stop {
+                return neighbor;
+            }
+            if accept(cost - current_cost, temperature, &mut rng) {
+                current = neighbor;
+                current_cost = cost;
+                if current_cost < global_best_cost {
+                    global_best = current.clone();
+                    global_best_cost = current_cost;
+                }
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        println!("SA iteration = {}", iteration);
+
+        global_best
+    }
+}
+
+impl SA {
+    /// * `temperature` - 初始溫度，應與成本的差距在同一個數量級
+    pub fn new(network: &Network, seed: u64, cooling: Cooling, temperature: f64) -> Self {
+        let mut yens = Yens::new(network, MAX_K);
+        yens.compute(network);
+        SA { yens, seed, cooling, temperature }
+    }
+    fn temperature_at(&self, iteration: usize, start: Instant, deadline: Instant) -> f64 {
+        let initial = self.temperature;
+        match self.cooling {
+            Cooling::Geometric(rate) => initial * rate.powf(iteration as f64),
+            Cooling::Linear          => {
+                let total = deadline.saturating_duration_since(start).as_secs_f64();
+                let elapsed = start.elapsed().as_secs_f64();
+                match total > 0.0 {
+                    true  => initial * (1.0 - elapsed / total).max(0.0),
+                    false => 0.0,
+                }
+            }
+            Cooling::Logarithmic     => initial * 2f64.ln() / (iteration as f64 + 2.0).ln(),
+        }
+    }
+}
+
+/// Metropolis 準則：較好的解必定接受，較差的解以 `exp(-delta / T)` 的機率接受
+fn accept(delta: f64, temperature: f64, rng: &mut ChaChaRng) -> bool {
+    if delta <= 0.0 {
+        return true;
+    }
+    if temperature < MIN_TEMPERATURE {
+        return false;
+    }
+    rng.gen_bool((-delta / temperature).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnc::CNC;
+    use crate::utils::yaml;
+
+    #[test]
+    fn it_cools_down_by_schedule() {
+        let network = Network::new();
+        let start = Instant::now();
+        let deadline = start + std::time::Duration::from_secs(3600);
+        let sa = SA::new(&network, 0, Cooling::Geometric(0.5), 100.0);
+        assert_eq!(sa.temperature_at(0, start, deadline), 100.0);
+        assert_eq!(sa.temperature_at(2, start, deadline), 25.0);
+        // the exponent would wrap around as an i32
+        assert_eq!(sa.temperature_at(1 << 32, start, deadline), 0.0);
+        let sa = SA::new(&network, 0, Cooling::Logarithmic, 100.0);
+        assert!((sa.temperature_at(0, start, deadline) - 100.0).abs() < 1e-9);
+        assert!((sa.temperature_at(2, start, deadline) - 50.0).abs() < 1e-9);
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        assert!(accept(-1.0, 0.0, &mut rng));
+        assert!(!accept(1.0, 0.0, &mut rng));
+    }
+
+    #[test]
+    fn it_validates_cooling_only_for_sa() {
+        let config = || {
+            let mut config = yaml::load_config("data/config/default.yaml");
+            config.cooling_rate = 1.5;
+            config
+        };
+        CNC::new(Network::new(), config());
+        let mut sa = config();
+        sa.algorithm = String::from("sa");
+        assert!(std::panic::catch_unwind(|| CNC::new(Network::new(), sa)).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::Instant;
+
+use super::Algorithm;
+use crate::cnc::Toolbox;
+use crate::component::Solution;
+use crate::network::{EdgeIndex, Network, NodeIndex, Path};
+
+// "IEEE Standard for Local and Metropolitan Area Networks--Bridges and Bridged Networks," in IEEE
+// Std 802.1Q-2018, clause 13, doi: 10.1109/IEEESTD.2018.8403927.
+
+/// 所有資料流都沿著同一棵生成樹繞送，模擬沒有 CNC 時 (M)STP 的行為
+///
+/// 每對端點之間只有一條候選路徑，因此不會重新路由。
+pub struct STP {
+    paths: Vec<Vec<Vec<Path>>>,
+}
+
+impl Algorithm for STP {
+    fn candidates(&self, src: usize, dst: usize) -> &Vec<Path> {
+        &self.paths[src][dst]
+    }
+    fn configure(&mut self, mut last_run: Solution, _deadline: Instant, toolbox: Toolbox) -> Solution {
+        toolbox.evaluate_cost(&mut last_run);
+        last_run
+    }
+}
+
+impl STP {
+    /// * `root` - 根橋接器，未指定時如同 STP 選出編號最小的橋接器
+    pub fn new(network: &Network, root: Option<usize>) -> Self {
+        let node_count = network.node_count();
+        if let Some(root) = root {
+            if root >= node_count {
+                panic!("Failed specify a root bridge beyond the network");
+            }
+            if network.end_devices.contains(&root.into()) {
+                panic!("Failed specify an end device as the root bridge");
+            }
+        }
+        let root = root
+            .or_else(|| (0..node_count).find(|&ix| !network.end_devices.contains(&ix.into())))
+            .expect("Failed to find a root bridge");
+        let parents = span(network, root.into());
+        let spanned = |end: NodeIndex| end.index() == root || parents[end.index()].is_some();
+        // 不在生成樹上的端點之間沒有路徑，有資料流經過時由 `FlowTable` 回報
+        let mut paths = vec![vec![vec![]; node_count]; node_count];
+        for &src in &network.end_devices {
+            for &dst in &network.end_devices {
+                if src == dst || !spanned(src) || !spanned(dst) { continue; }
+                let path = route_along(network, &parents, src, dst);
+                paths[src.index()][dst.index()] = vec![path];
+            }
+        }
+        STP { paths }
+    }
+}
+
+/// 以路徑成本（與頻寬成反比）建立以 `root` 為根的最短路徑樹，端點裝置不轉送
+///
+/// 回傳每個節點連向其父節點的邊（方向為父節點到子節點），成本相同時保留先找到者。
+fn span(network: &Network, root: NodeIndex) -> Vec<Option<EdgeIndex>> {
+    let node_count = network.node_count();
+    let mut dist = vec![f64::INFINITY; node_count];
+    let mut parents = vec![None; node_count];
+    let mut settled = vec![false; node_count];
+    dist[root.index()] = 0.0;
+    loop {
+        let next = (0..node_count)
+            .filter(|&ix| !settled[ix] && dist[ix].is_finite())
+            .min_by(|&x, &y| dist[x].partial_cmp(&dist[y]).unwrap());
+        let v = match next {
+            Some(v) => v,
+            None    => break,
+        };
+        settled[v] = true;
+        if v != root.index() && network.end_devices.contains(&v.into()) {
+            continue;
+        }
+        for vu in network.outgoings(v.into()) {
+            let u = network.endpoints(vu).1.index();
+            let cost = dist[v] + network.duration_on(vu, 1);
+            if !settled[u] && cost < dist[u] {
+                dist[u] = cost;
+                parents[u] = Some(vu);
+            }
+        }
+    }
+    parents
+}
+
+/// 生成樹上從 `src` 往上到共同祖先，再往下到 `dst` 的路徑
+fn route_along(network: &Network, parents: &[Option<EdgeIndex>], src: NodeIndex, dst: NodeIndex) -> Path {
+    let climb = |mut node: NodeIndex| {
+        let mut edges = vec![];
+        while let Some(edge) = parents[node.index()] {
+            edges.push(edge);
+            node = network.endpoints(edge).0;
+        }
+        edges
+    };
+    let mut ups = climb(src);
+    let mut downs = climb(dst);
+    while !ups.is_empty() && ups.last() == downs.last() {
+        ups.pop();
+        downs.pop();
+    }
+    // 連線皆成對加入，相鄰編號的邊即為反方向
+    ups.iter()
+        .map(|edge| EdgeIndex::from(edge.index() ^ 1))
+        .chain(downs.into_iter().rev())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_routes_along_tree_of_root() {
+        //     ┌───[3]───(1)
+        // (0)─[2]    │
+        //     └───[4]┘
+        let mut network = Network::new();
+        network.add_nodes(2, 3);
+        network.add_edges(vec![
+            (0, 2, 100.0), (1, 3, 100.0), (2, 3, 100.0), (2, 4, 100.0), (4, 3, 100.0),
+        ]);
+        let route = |root: Option<usize>| {
+            let stp = STP::new(&network, root);
+            let forth = network.node_sequence(&stp.candidates(0, 1)[0]);
+            let back = network.node_sequence(&stp.candidates(1, 0)[0]);
+            (forth, back)
+        };
+        assert_eq!(route(None), (vec![0, 2, 3, 1], vec![1, 3, 2, 0]));
+        assert_eq!(route(Some(4)), (vec![0, 2, 4, 3, 1], vec![1, 3, 4, 2, 0]));
+    }
+}
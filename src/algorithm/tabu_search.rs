@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+
+use super::base::yens::Yens;
+use super::Algorithm;
+use crate::cnc::Toolbox;
+use crate::component::Solution;
+use crate::network::{Network, Path};
+use crate::MAX_K;
+
+/// 禁忌名單的長度，即一個 (資料流, 路徑) 在幾步之內不能再被選回
+const TABU_TENURE: usize = 7;
+/// 每次迭代考慮幾條延遲違反最嚴重的 AVB 資料流
+const CRITICAL_STREAMS: usize = 6;
+/// 每次迭代額外隨機考慮幾條資料流，避免只在同一批資料流中打轉
+const RANDOM_STREAMS: usize = 2;
+
+// F. Glover, "Tabu Search—Part I," ORSA Journal on Computing, vol. 1, no. 3, pp. 190-206, 1989,
+// doi: 10.1287/ijoc.1.3.190.
+
+pub struct TS {
+    yens: Yens,
+    seed: u64,
+}
+
+/// 最近被換掉的 (資料流, 路徑)，超過 `tenure` 步後自動解禁
+struct TabuList {
+    moves: VecDeque<(usize, usize)>,
+    tenure: usize,
+}
+
+impl Algorithm for TS {
+    fn candidates(&self, src: usize, dst: usize) -> &Vec<Path> {
+        self.yens.k_shortest_paths(src.into(), dst.into())
+    }
+    /// 每次迭代走向鄰域中最好的非禁忌解，即使它比目前的解更差；
+    /// 禁忌的移動若能得到歷史最佳解則仍可採用（aspiration）
+    fn configure(&mut self, last_run: Solution, deadline: Instant, toolbox: Toolbox) -> Solution {
+        let flowtable = last_run.flowtable();
+        let mut rng = ChaChaRng::seed_from_u64(self.seed);
+        let counts = (0..flowtable.len())
+            .map(|nth| flowtable.ends(nth))
+            .map(|(src, dst)| self.candidates(src, dst).len())
+            .collect::<Vec<_>>();
+        let mut tabu = TabuList::new(TABU_TENURE);
+
+        let mut current = last_run;
+        let (cost, stop) = toolbox.evaluate_cost(&mut current);
+        let mut global_best = current.clone();
+        let mut global_best_cost = cost;
+        if stop {
+            return global_best;
+        }
+
+        #[allow(unused_variables)]
+        let mut epoch = 0;
+        'outer: while Instant::now() < deadline {
+            epoch += 1;
+            // 延遲超過 deadline 最多者優先，其次為餘裕最少者
+            let mut streams = flowtable.avbs().iter()
+                .filter(|&&avb| counts[avb] > 1)
+                .map(|&avb| {
+                    let kth = current.selection(avb).next().unwrap();
+                    let wcd = toolbox.evaluate_wcd(avb, kth, &current) as i64;
+                    (wcd - flowtable.avb_spec(avb).deadline as i64, avb)
+                })
+                .collect::<Vec<_>>();
+            streams.sort_unstable_by(|x, y| y.cmp(x));
+            let mut streams = streams.into_iter()
+                .map(|(_, avb)| avb)
+                .take(CRITICAL_STREAMS)
+                .collect::<Vec<_>>();
+            for _ in 0..RANDOM_STREAMS {
+                let nth = rng.gen_range(0..counts.len());
+                if counts[nth] > 1 && !streams.contains(&nth) {
+                    streams.push(nth);
+                }
+            }
+
+            let mut best_move: Option<(f64, usize, usize, Solution)> = None;
+            for &nth in &streams {
+                let old_kth = current.selection(nth).next().unwrap();
+                for kth in (0..counts[nth]).filter(|&kth| kth != old_kth) {
+                    if Instant::now() > deadline {
+                        break 'outer;
+                    }
+                    let mut neighbor = current.clone();
+                    neighbor.select(nth, kth);
+                    let (cost, stop) = toolbox.evaluate_cost(&mut neighbor);
+                    if stop {
+                        global_best = neighbor;
+                        break 'outer;
+                    }
+                    let aspirated = cost < global_best_cost;
+                    if tabu.contains(nth, kth) && !aspirated {
+                        continue;
+                    }
+                    if best_move.as_ref().is_none_or(|(best, ..)| cost < *best) {
+                        best_move = Some((cost, nth, old_kth, neighbor));
+                    }
+                }
+            }
+
+            let (cost, nth, old_kth, neighbor) = match best_move {
+                Some(best_move) => best_move,
+                None            => continue, // 所有移動皆為禁忌，換一批資料流
+            };
+            tabu.push(nth, old_kth);
+            current = neighbor;
+            if cost < global_best_cost {
+                global_best = current.clone();
+                global_best_cost = cost;
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        println!("TS epoch = {}", epoch);
+
+        global_best
+    }
+}
+
+impl TS {
+    pub fn new(network: &Network, seed: u64) -> Self {
+        let mut yens = Yens::new(network, MAX_K);
+        yens.compute(network);
+        TS { yens, seed }
+    }
+}
+
+impl TabuList {
+    fn new(tenure: usize) -> Self {
+        TabuList { moves: VecDeque::with_capacity(tenure), tenure }
+    }
+    fn push(&mut self, nth: usize, kth: usize) {
+        if self.moves.len() == self.tenure {
+            self.moves.pop_front();
+        }
+        self.moves.push_back((nth, kth));
+    }
+    fn contains(&self, nth: usize, kth: usize) -> bool {
+        self.moves.contains(&(nth, kth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_forgets_moves_after_tenure() {
+        let mut tabu = TabuList::new(2);
+        tabu.push(0, 1);
+        tabu.push(3, 0);
+        assert!(tabu.contains(0, 1));
+        assert!(!tabu.contains(0, 0));
+        tabu.push(5, 2);
+        assert!(!tabu.contains(0, 1));
+        assert!(tabu.contains(3, 0));
+        assert!(tabu.contains(5, 2));
+    }
+}
@@ -1,11 +1,14 @@
 use std::fmt::Write;
-use std::rc::{Rc, Weak};
+use std::fs;
+use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
-use crate::algorithm::{Algorithm, AlgorithmEnum, ACO, RO, SPF};
-use crate::component::{Evaluator, FlowTable, Solution};
+use crate::algorithm::{Algorithm, AlgorithmEnum, Cooling, Criterion, Crossover, Scope, Sequence};
+use crate::algorithm::{ACO, BNB, CGR, ECMP, GA, NSGA, RO, SA, SPF, STP, TS};
+use crate::component::{Analysis, Evaluator, FlowTable, Isolation, Objective, Objectives, Solution};
 use crate::network::Network;
-use crate::scheduler::Scheduler;
+use crate::scheduler::{OrderBy, Scheduler};
+use crate::simulator::Simulator;
 use crate::utils::config::Config;
 use crate::utils::stream::{AVB, TSN};
 
@@ -13,10 +16,11 @@ pub struct CNC {
     pub algorithm: AlgorithmEnum,
     pub scheduler: Scheduler,
     pub evaluator: Evaluator,
-    pub flowtable: Rc<FlowTable>,
+    pub flowtable: Arc<FlowTable>,
     pub solution: Solution,
+    latest: Solution,
     #[allow(dead_code)]
-    pub network: Rc<Network>,
+    pub network: Arc<Network>,
     pub config: Config,
 }
 
@@ -29,34 +33,144 @@ pub struct Toolbox<'a> {
 
 impl CNC {
     pub fn new(graph: Network, config: Config) -> Self {
-        let mut weights = config.weights;
+        let mut weights = config.objectives.iter()
+            .map(|(name, &weight)| match Objective::from_name(name) {
+                Some(obj) => (obj, weight),
+                None      => panic!("Failed specify an unknown objective"),
+            })
+            .collect::<Vec<_>>();
+        if let Some(legacy) = config.weights {
+            if !weights.is_empty() {
+                panic!("Failed specify both objectives and the legacy weights");
+            }
+            weights = Objective::LEGACY.iter().cloned().zip(legacy).collect();
+        }
+        // 被拒絕的 AVB 資料流不計入失敗數及最差延遲，未指定權重時比照 AVB 失敗，以免拒絕反而降低成本
+        let weight_of = |objective| weights.iter()
+            .find(|&&(obj, _)| obj == objective)
+            .map(|&(_, weight)| weight);
+        if weight_of(Objective::AVBRejected).is_none() {
+            match weight_of(Objective::AVBFailed) {
+                Some(weight) => weights.push((Objective::AVBRejected, weight)),
+                None if weight_of(Objective::AVBWCD).is_some()
+                    => panic!("Failed specify AVB objectives without a weight on rejected AVBs"),
+                None => (),
+            }
+        }
+        weights.sort_by_key(|&(obj, _)| obj);
         if config.algorithm == "ro" {
-            weights[2] = 0.0;
+            weights.iter_mut()
+                .filter(|(obj, _)| *obj == Objective::Rerouted)
+                .for_each(|(_, weight)| *weight = 0.0);
         }
+        if config.algorithm == "sa" {
+            if config.cooling_rate <= 0.0 || config.cooling_rate >= 1.0 {
+                panic!("Failed specify SA cooling_rate out of (0, 1)");
+            }
+            if config.temperature <= 0.0 {
+                panic!("Failed specify SA temperature not above 0");
+            }
+        }
+        let cooling = match config.cooling.as_str() {
+            "geometric"   => Cooling::Geometric(config.cooling_rate),
+            "linear"      => Cooling::Linear,
+            "logarithmic" => Cooling::Logarithmic,
+            _             => panic!("Failed specify an unknown cooling schedule"),
+        };
+        let crossover = match config.crossover.as_str() {
+            "uniform" => Crossover::Uniform,
+            "segment" => Crossover::Segment,
+            _         => panic!("Failed specify an unknown crossover operator"),
+        };
+        let sequence = match config.greedy_order.as_str() {
+            "index"       => Sequence::Index,
+            "deadline"    => Sequence::Deadline,
+            "utilization" => Sequence::Utilization,
+            _             => panic!("Failed specify an unknown greedy order"),
+        };
+        let criterion = match config.greedy_criterion.as_str() {
+            "load" => Criterion::Load,
+            "wcd"  => Criterion::WCD,
+            _      => panic!("Failed specify an unknown greedy criterion"),
+        };
+        let scope = match config.ro_scope.as_str() {
+            "avb"   => Scope::AVB,
+            "joint" => Scope::Joint,
+            _       => panic!("Failed specify an unknown RO scope"),
+        };
         let algorithm: AlgorithmEnum = match config.algorithm.as_str() {
-            "aco" => ACO::new(&graph, config.seed, config.parameters.clone()).into(),
-            "ro"  => RO::new(&graph, config.seed).into(),
-            "spf" => SPF::new(&graph).into(),
-            _     => panic!("Failed specify an unknown routing algorithm"),
+            "aco"   => ACO::new(&graph, config.seed, config.parameters.clone()).into(),
+            "bnb"   => BNB::new(&graph).into(),
+            "cgr"   => CGR::new(&graph, sequence, criterion).into(),
+            "ecmp"  => ECMP::new(&graph, config.seed).into(),
+            "ga"    => GA::new(&graph, config.seed, crossover).into(),
+            "nsga2" => NSGA::new(&graph, config.seed).into(),
+            "ro"    => RO::new(&graph, config.seed, scope).into(),
+            "sa"    => SA::new(&graph, config.seed, cooling, config.temperature).into(),
+            "spf"   => SPF::new(&graph).into(),
+            "stp"   => STP::new(&graph, config.root_bridge).into(),
+            "tabu"  => TS::new(&graph, config.seed).into(),
+            _       => panic!("Failed specify an unknown routing algorithm"),
         };
-        let flowtable = Rc::new(FlowTable::new());
+        let flowtable = Arc::new(FlowTable::new());
         let mut solution = Solution::new(&graph);
-        let network = Rc::new(graph);
-        solution.flowtable = Rc::downgrade(&flowtable);
-        solution.network = Rc::downgrade(&network);
-        let scheduler = Scheduler::new();
-        let evaluator = Evaluator::new(weights);
-        Self { algorithm, scheduler, evaluator, solution, flowtable, network, config }
+        if config.guard_band {
+            solution.allocated_tsns.reserve_guard_bands(&graph);
+        }
+        let network = Arc::new(graph);
+        solution.flowtable = Arc::downgrade(&flowtable);
+        solution.network = Arc::downgrade(&network);
+        if !(0.0..=1.0).contains(&config.be_load) {
+            panic!("Failed specify a BE load outside [0, 1]");
+        }
+        if config.isolation == "none" && config.queue_depth < 1 {
+            panic!("Failed specify a queue depth less than 1");
+        }
+        let isolation = match config.isolation.as_str() {
+            "legacy" => Isolation::Legacy,
+            "stream" => Isolation::Stream,
+            "frame"  => Isolation::Frame,
+            "none"   => Isolation::Shared(config.queue_depth),
+            _        => panic!("Failed specify an unknown queue isolation"),
+        };
+        let orderings = config.orderings.iter()
+            .map(|order| match order.as_str() {
+                "deadline"    => OrderBy::Deadline,
+                "laxity"      => OrderBy::Laxity,
+                "size"        => OrderBy::Size,
+                "utilization" => OrderBy::Utilization,
+                "hops"        => OrderBy::Hops,
+                "random"      => OrderBy::Random,
+                _             => panic!("Failed specify an unknown TSN ordering"),
+            })
+            .collect();
+        let shares = config.avb_shares.iter()
+            .map(|(&class, &share)| match class {
+                'A' | 'B' => (class, share),
+                _         => panic!("Failed specify an unknown SR class"),
+            })
+            .collect();
+        let scheduler = Scheduler::new(isolation, orderings, shares, config.seed);
+        let analysis = match config.analysis.as_str() {
+            "legacy" => Analysis::Legacy,
+            "cbs"    => Analysis::CBS,
+            "nc"     => Analysis::NC,
+            _        => panic!("Failed specify an unknown AVB analysis"),
+        };
+        let evaluator = Evaluator::new(weights, analysis);
+        let latest = Solution::default();
+        Self { algorithm, scheduler, evaluator, solution, latest, flowtable, network, config }
     }
     pub fn add_streams(&mut self, tsns: Vec<TSN>, avbs: Vec<AVB>) {
         self.solution.flowtable = Weak::new();
+        self.latest = Solution::default();
         // ensure everyone drops their ownerships
-        debug_assert!(Rc::weak_count(&self.flowtable) == 0);
-        let flowtable = Rc::get_mut(&mut self.flowtable).unwrap();
+        debug_assert!(Arc::weak_count(&self.flowtable) == 0);
+        let flowtable = Arc::get_mut(&mut self.flowtable).unwrap();
         flowtable.append(tsns, avbs);
         flowtable.append_candidates(&self.algorithm);
         self.solution.resize(self.flowtable.len());
-        self.solution.flowtable = Rc::downgrade(&self.flowtable);
+        self.solution.flowtable = Arc::downgrade(&self.flowtable);
     }
     pub fn configure(&mut self) -> u128 {
         let scheduler = &self.scheduler;
@@ -72,10 +186,21 @@ impl CNC {
         let elapsed = start.elapsed().as_micros();
 
         self.show_results(&this_run);
-        self.solution = this_run;
+        if let AlgorithmEnum::NSGA(nsga) = &self.algorithm {
+            self.export_pareto_front(nsga.front());
+        }
+        if config.simulate > 0 {
+            let simulator = Simulator::new(config.simulate, config.be_load, config.seed);
+            print!("{}", simulator.validate(&this_run, evaluator));
+        }
+        self.latest = std::mem::replace(&mut self.solution, this_run);
 
         elapsed
     }
+    /// 上一次設定開始時的解，即該次設定計算重新路由等目標時比較的對象；加入資料流後清空
+    pub fn latest(&self) -> &Solution {
+        &self.latest
+    }
     fn show_results(&self, current: &Solution) {
         let flowtable = &self.flowtable;
         let network = &self.network;
@@ -86,28 +211,75 @@ impl CNC {
 
         writeln!(msg, "TSN streams").unwrap();
         for &tsn in flowtable.tsns() {
-            let outcome = if current.outcome(tsn).is_unschedulable()
+            let outcome = if self.evaluator.evaluate_tsn_failure(tsn, current)
                 { "failed" } else { "ok" };
             let kth = current.selection(tsn).current().unwrap();
             let route = network.node_sequence(flowtable.candidate(tsn, kth));
-            writeln!(msg, "- stream #{:02} {}, with route #{} {:?}",
-                     tsn, outcome, kth, route).unwrap();
+            let offset = current.offset(tsn);
+            let timing = match self.evaluator.evaluate_tsn_timing(tsn, current) {
+                Some(t) => format!(", latency {}, jitter {}, slack {}", t.latency, t.jitter, t.slack),
+                None    => String::new(),
+            };
+            if flowtable.tsn_spec(tsn).is_shaped() {
+                let bound = self.evaluator.evaluate_tsn_ats_bound(tsn, current) as f64;
+                let max = flowtable.tsn_spec(tsn).deadline as f64;
+                writeln!(msg, "- stream #{:02} {} (offset {}, {:02.0}% by ats{}), with route #{} {:?}",
+                         tsn, outcome, offset, bound / max * 100.0, timing, kth, route).unwrap();
+                continue;
+            }
+            writeln!(msg, "- stream #{:02} {} (offset {}{}), with route #{} {:?}",
+                     tsn, outcome, offset, timing, kth, route).unwrap();
         }
         writeln!(msg, "AVB streams").unwrap();
         for &avb in flowtable.avbs() {
             let objs = self.evaluator.evaluate_avb_objectives(avb, current, latest);
             let max = flowtable.avb_spec(avb).deadline as f64;
-            let outcome = if objs[1] == 0.0 { "ok" } else { "failed" };
-            let reroute = if objs[2] == 0.0 { "" } else { "*" };
+            let outcome = if objs.get(Objective::AVBFailed) == 0.0 { "ok" } else { "failed" };
+            let reroute = if objs.get(Objective::Rerouted) == 0.0 { "" } else { "*" };
             let kth = current.selection(avb).current().unwrap();
             let route = network.node_sequence(flowtable.candidate(avb, kth));
+            if objs.get(Objective::AVBRejected) > 0.0 {
+                writeln!(msg, "- stream #{:02} rejected, with route #{}{} {:?}",
+                         avb, kth, reroute, route).unwrap();
+                continue;
+            }
             writeln!(msg, "- stream #{:02} {} ({:02.0}%), with route #{}{} {:?}",
-                     avb, outcome, objs[3] / max * 100.0, kth, reroute, route).unwrap();
+                     avb, outcome, objs.get(Objective::AVBWCD) / max * 100.0, kth, reroute, route).unwrap();
         }
-        writeln!(msg, "the solution has cost {:.2} and each objective {:.2?}",
+        writeln!(msg, "link utilization").unwrap();
+        let utilizations = self.evaluator.evaluate_link_utilizations(current);
+        for (ix, utilization) in utilizations.iter().enumerate() {
+            if utilization.total() == 0.0 { continue; }
+            let ends = network.node_sequence(&[ix.into()]);
+            writeln!(msg, "- link #{:02} {:?}: tsn {:5.2}%, avb {:5.2}%, total {:5.2}%",
+                     ix, ends, utilization.tsn * 100.0, utilization.avb * 100.0,
+                     utilization.total() * 100.0).unwrap();
+        }
+        writeln!(msg, "the solution has cost {:.2} and each objective {}",
                  cost, objs).unwrap();
         print!("{}", msg);
     }
+    /// 將 Pareto front 輸出為 CSV，每列為一組目標值及每條資料流選用的路徑
+    fn export_pareto_front(&self, front: &[(Objectives, Vec<usize>)]) {
+        let mut csv = String::new();
+        let names = self.evaluator.objectives().iter()
+            .map(|obj| obj.name())
+            .collect::<Vec<_>>();
+        writeln!(csv, "{},selections", names.join(",")).unwrap();
+        for (objs, selections) in front {
+            let values = self.evaluator.objectives().iter()
+                .map(|&obj| objs.get(obj).to_string())
+                .collect::<Vec<_>>();
+            let selections = selections.iter()
+                .map(|kth| kth.to_string())
+                .collect::<Vec<_>>();
+            writeln!(csv, "{},{}", values.join(","), selections.join(" ")).unwrap();
+        }
+        match &self.config.pareto_front {
+            Some(path) => fs::write(path, csv).expect("Failed to write pareto front"),
+            None       => print!("pareto front\n{}", csv),
+        }
+    }
 }
 
 impl<'a> Toolbox<'a> {
@@ -118,10 +290,34 @@ impl<'a> Toolbox<'a> {
     pub fn evaluate_wcd(&'a self, avb: usize, kth: usize, solution: &Solution) -> u32 {
         self.evaluator.evaluate_avb_wcd_for_kth(avb, kth, solution)
     }
+    pub fn objectives(&self) -> Vec<Objective> {
+        self.evaluator.objectives()
+    }
+    pub fn evaluate_objectives(&'a self, solution: &mut Solution) -> Objectives {
+        self.scheduler.configure(solution); // where it's mutated
+        self.evaluator.update_avb_wcds(solution);
+        self.evaluator.evaluate_objectives(solution, self.latest)
+    }
+    /// 無法排入 GCL 或超過延遲上界的 TSN 資料流數，須在計算成本之後呼叫
+    ///
+    /// 只有指定抖動上限的資料流需要計算時序，其餘只看排程結果或延遲上界。
+    pub fn count_tsn_failures(&'a self, solution: &Solution) -> usize {
+        solution.flowtable().tsns().iter()
+            .filter(|&&tsn| self.evaluator.evaluate_tsn_failure(tsn, solution))
+            .count()
+    }
+    /// 編號小於 `fixed` 的資料流已決定路徑時，成本的下界
+    pub fn evaluate_lower_bound(&'a self, solution: &Solution, fixed: usize) -> f64 {
+        self.evaluator.evaluate_cost_lower_bound(solution, self.latest, fixed)
+    }
     pub fn evaluate_cost(&'a self, solution: &mut Solution) -> (f64, bool) {
         self.scheduler.configure(solution); // where it's mutated
+        self.evaluator.update_avb_wcds(solution);
         let (cost, objs) = self.evaluator.evaluate_cost_objectives(solution, self.latest);
-        let stop = self.config.early_stop && objs[0] == 0.0 && objs[1] == 0.0;
+        let stop = self.config.early_stop
+            && objs.get(Objective::TSNFailed) == 0.0
+            && objs.get(Objective::AVBFailed) == 0.0
+            && objs.get(Objective::AVBRejected) == 0.0;
         (cost, stop)
     }
 }
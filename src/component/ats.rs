@@ -0,0 +1,58 @@
+use crate::utils::stream::TSN;
+
+/// 每條 ATS 資料流各自的 token bucket 整形器 (802.1Qcr)
+/// * `burst` - 允許的最大突發量，取一個週期內送出的資料量
+/// * `rate` - 長期平均速率，單位為 位元組 / 微秒
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TokenBucket {
+    pub burst: u32,
+    pub rate: f64,
+}
+
+/// 輸出埠上的 interleaved regulator，彙整所有經過此埠的 ATS 資料流
+///
+/// 由於 interleaved regulator 會在每一跳重新整形，資料流的突發量不會沿路徑累積，
+/// 因此每一跳都能以資料流原本的 token bucket 計算（shaping-for-free）。
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PortRegulator {
+    pub burst: u32,
+    pub rate: f64,
+}
+
+impl TokenBucket {
+    pub fn new(burst: u32, rate: f64) -> Self {
+        TokenBucket { burst, rate }
+    }
+    pub fn from_spec(spec: &TSN) -> Self {
+        TokenBucket::new(spec.size, spec.size as f64 / spec.period as f64)
+    }
+}
+
+impl PortRegulator {
+    pub fn new() -> Self {
+        PortRegulator { ..Default::default() }
+    }
+    pub fn admit(&mut self, bucket: &TokenBucket) {
+        self.burst += bucket.burst;
+        self.rate += bucket.rate;
+    }
+    /// 彙整的長期速率必須低於連線頻寬，否則佇列會無限制地成長
+    pub fn is_stable(&self, bandwidth: f64) -> bool {
+        self.rate < bandwidth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_aggregates_token_buckets() {
+        let mut port = PortRegulator::new();
+        port.admit(&TokenBucket::from_spec(&TSN::new(0, 1, 300, 100, 100, 0)));
+        port.admit(&TokenBucket::from_spec(&TSN::new(0, 1, 500, 250, 250, 0)));
+        assert_eq!(port, PortRegulator { burst: 800, rate: 5.0 });
+        assert!(!port.is_stable(5.0));
+        assert!(port.is_stable(100.0));
+    }
+}
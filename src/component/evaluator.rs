@@ -1,45 +1,243 @@
 use std::cmp::max;
 
 use super::Solution;
-use crate::component::{FlowTable, GateCtrlList};
-use crate::network::{EdgeIndex, Network, MTU};
+use crate::component::netcalc::{self, ArrivalCurve, ServiceCurve};
+use crate::component::{FlowTable, GateCtrlList, Objective, Objectives, PortRegulator, TokenBucket};
+use crate::network::{EdgeIndex, Network, Path, MTU};
 
 /// AVB 資料流最多可以佔用的資源百分比（模擬 Credit Base Shaper 的效果）
 const MAX_AVB_SETTING: f64 = 0.75;
+/// 網路演算逐跳傳遞到達曲線時，最多反覆計算的次數
+const MAX_NC_ITERATIONS: usize = 100;
+/// 突發量的變化小於此值（位元組）即視為收斂
+const NC_TOLERANCE: f64 = 1e-6;
+
+/// AVB 資料流最差延遲的分析方式
+/// * `Legacy` - 原本的近似分析，以固定的頻寬比例模擬 CBS，保留以便和舊實驗比較
+/// * `CBS` - 依 802.1BA 的 credit-based shaper 分析，考慮 idle slope、信用恢復、低優先權阻擋及 GCL 關閉
+/// * `NC` - 以網路演算推導服務曲線，端對端的突發量只需付一次
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Analysis {
+    #[default]
+    Legacy,
+    CBS,
+    NC,
+}
+
+/// TSN 資料流的端對端時間特性
+/// * `latency` - 所有實例中最差的端對端延遲
+/// * `jitter` - 最差與最佳延遲的差
+/// * `slack` - 最差延遲距離 deadline 的餘裕，負值代表超過
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    pub latency: u32,
+    pub jitter: u32,
+    pub slack: i64,
+}
+
+impl Timing {
+    fn new(worst: u32, best: u32, deadline: u32) -> Self {
+        Timing {
+            latency: worst,
+            jitter: worst - best,
+            slack: deadline as i64 - worst as i64,
+        }
+    }
+}
+
+/// 連線的頻寬使用率，皆為佔用時間的比例
+/// * `tsn` - TAS 整形者以 GCL 中的閘門時間計算，ATS 整形者以平均速率計算
+/// * `avb` - 經過此連線的 AVB 資料流之平均速率
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Utilization {
+    pub tsn: f64,
+    pub avb: f64,
+}
+
+impl Utilization {
+    pub fn total(&self) -> f64 {
+        self.tsn + self.avb
+    }
+}
 
 #[derive(Default)]
 pub struct Evaluator {
-    weights: [f64; 4],
+    weights: Vec<(Objective, f64)>,
+    analysis: Analysis,
 }
 
 impl Evaluator {
-    pub fn new(weights: [f64; 4]) -> Self {
-        Evaluator { weights }
+    /// * `weights` - 啟用的目標及其權重，未列出者不計入成本
+    pub fn new(weights: Vec<(Objective, f64)>, analysis: Analysis) -> Self {
+        Evaluator { weights, analysis }
+    }
+    pub fn objectives(&self) -> Vec<Objective> {
+        self.weights.iter().map(|&(obj, _)| obj).collect()
+    }
+    pub fn is_enabled(&self, objective: Objective) -> bool {
+        self.weights.iter().any(|&(obj, _)| obj == objective)
     }
     pub fn evaluate_avb_wcd(&self, avb: usize, solution: &Solution) -> u32 {
         let kth = solution.selection(avb).next().unwrap();
         self.evaluate_avb_wcd_for_kth(avb, kth, solution)
     }
-    pub fn evaluate_avb_objectives(&self, avb: usize, solution: &Solution, latest: &Solution) -> [f64; 4] {
+    pub fn evaluate_tsn_ats_bound(&self, tsn: usize, solution: &Solution) -> u32 {
+        let kth = solution.selection(tsn).next().unwrap();
+        self.evaluate_tsn_ats_bound_for_kth(tsn, kth, solution)
+    }
+    /// TAS 整形者看 GCL 是否排入，ATS 整形者看延遲上界是否超過 deadline，且抖動皆不得超過上限
+    ///
+    /// 只有設定抖動上限的資料流才需計算時間特性。TAS 整形者的每個實例都使用相同位移的窗口，
+    /// 抖動必定為零，因此抖動上限實際上只限制 ATS 整形者。
+    pub fn evaluate_tsn_failure(&self, tsn: usize, solution: &Solution) -> bool {
+        let flowtable = solution.flowtable();
+        let spec = flowtable.tsn_spec(tsn);
+        match spec.is_shaped() {
+            true  => {
+                let bound = self.evaluate_tsn_ats_bound(tsn, solution);
+                bound > spec.deadline || spec.jitter.is_some_and(|max_jitter|
+                    self.time_tsn_by_ats(tsn, bound, solution).jitter > max_jitter)
+            }
+            false => {
+                solution.outcome(tsn).is_unschedulable() || spec.jitter.is_some_and(|max_jitter|
+                    self.time_tsn_by_gcl(tsn, solution).is_some_and(|timing| timing.jitter > max_jitter))
+            }
+        }
+    }
+    /// 計算 TSN 資料流在超週期中所有實例的延遲、抖動及餘裕，排不進 GCL 者回傳 None
+    ///
+    /// TAS 整形者依最後一跳的閘門事件計算每個實例的延遲；ATS 整形者的延遲取其上界，
+    /// 抖動則為上界與不受任何干擾時的傳輸時間之差。
+    pub fn evaluate_tsn_timing(&self, tsn: usize, solution: &Solution) -> Option<Timing> {
+        let flowtable = solution.flowtable();
+        match flowtable.tsn_spec(tsn).is_shaped() {
+            true  => {
+                let bound = self.evaluate_tsn_ats_bound(tsn, solution);
+                Some(self.time_tsn_by_ats(tsn, bound, solution))
+            }
+            false => self.time_tsn_by_gcl(tsn, solution),
+        }
+    }
+    fn time_tsn_by_ats(&self, tsn: usize, bound: u32, solution: &Solution) -> Timing {
+        let flowtable = solution.flowtable();
+        let network = solution.network();
+        let spec = flowtable.tsn_spec(tsn);
+        let kth = solution.selection(tsn).next().unwrap();
+        let route = flowtable.candidate(tsn, kth);
+        let transmit = network.duration_along(route, spec.size).ceil() as u32;
+        Timing::new(bound, transmit.min(bound), spec.deadline)
+    }
+    fn time_tsn_by_gcl(&self, tsn: usize, solution: &Solution) -> Option<Timing> {
+        let flowtable = solution.flowtable();
+        let spec = flowtable.tsn_spec(tsn);
+        let kth = solution.selection(tsn).next().unwrap();
+        let route = flowtable.candidate(tsn, kth);
+        if solution.outcome(tsn).is_unschedulable() {
+            return None;
+        }
+        let offset = solution.offset(tsn);
+        let mut latencies: Vec<u32> = vec![];
+        for window in solution.allocated_tsns.get_stream_windows(*route.last()?, tsn) {
+            let instance = (window.start.saturating_sub(offset) / spec.period) as usize;
+            let release = offset + instance as u32 * spec.period;
+            if latencies.len() <= instance {
+                latencies.resize(instance + 1, 0);
+            }
+            latencies[instance] = max(latencies[instance], window.end - release);
+        }
+        let worst = latencies.iter().cloned().max()?;
+        let best = latencies.iter().cloned().filter(|&latency| latency > 0).min()?;
+        Some(Timing::new(worst, best, spec.deadline))
+    }
+    /// TSN 資料流最差的端對端延遲，排不進 GCL 者視為 deadline
+    pub fn evaluate_tsn_latency(&self, tsn: usize, solution: &Solution) -> u32 {
+        let flowtable = solution.flowtable();
+        let spec = flowtable.tsn_spec(tsn);
+        self.evaluate_tsn_timing(tsn, solution)
+            .map_or(spec.deadline, |timing| timing.latency)
+    }
+    /// 計算每條連線的頻寬使用率
+    ///
+    /// GCL 只記錄超週期中的窗口，因此閘門時間須除以超週期；若超週期短於資料流的週期，
+    /// 則只記錄了一個實例，改除以週期。
+    pub fn evaluate_link_utilizations(&self, solution: &Solution) -> Vec<Utilization> {
+        let flowtable = solution.flowtable();
+        let network = solution.network();
+        let gcl = &solution.allocated_tsns;
+        let mut utilizations = vec![Utilization::default(); network.edge_count()];
+        for &tsn in flowtable.tsns() {
+            let spec = flowtable.tsn_spec(tsn);
+            let kth = solution.selection(tsn).next().unwrap();
+            for &edge in flowtable.candidate(tsn, kth) {
+                let occupied = match spec.is_shaped() {
+                    true  => network.duration_on(edge, spec.size) / spec.period as f64,
+                    false => {
+                        let gated: u32 = gcl.get_stream_windows(edge, tsn).iter()
+                            .map(|window| window.end - window.start)
+                            .sum();
+                        gated as f64 / max(gcl.hyperperiod(), spec.period) as f64
+                    }
+                };
+                utilizations[edge.index()].tsn += occupied;
+            }
+        }
+        for (ix, utilization) in utilizations.iter_mut().enumerate() {
+            let edge = EdgeIndex::from(ix);
+            for avb in solution.traversed_avbs.iter(edge) {
+                let spec = flowtable.avb_spec(avb);
+                utilization.avb += network.duration_on(edge, spec.size) / spec.period as f64;
+            }
+        }
+        utilizations
+    }
+    /// 所有連線中最高的頻寬使用率
+    pub fn evaluate_max_link_utilization(&self, solution: &Solution) -> f64 {
+        self.evaluate_link_utilizations(solution).iter()
+            .map(|utilization| utilization.total())
+            .fold(0.0, f64::max)
+    }
+    /// 所有連線頻寬使用率的變異數，沒有承載任何資料流的連線也計入
+    pub fn evaluate_utilization_variance(&self, solution: &Solution) -> f64 {
+        let totals = self.evaluate_link_utilizations(solution).iter()
+            .map(|utilization| utilization.total())
+            .collect::<Vec<_>>();
+        if totals.is_empty() { return 0.0; }
+        let mean = totals.iter().sum::<f64>() / totals.len() as f64;
+        totals.iter().map(|total| (total - mean).powi(2)).sum::<f64>() / totals.len() as f64
+    }
+    pub fn count_gcl_entries(&self, solution: &Solution) -> usize {
+        let network = solution.network();
+        (0..network.edge_count())
+            .map(|ix| solution.allocated_tsns.get_gate_events(ix.into()).len())
+            .sum()
+    }
+    /// 被拒絕的 AVB 資料流沒有保留頻寬，不計算其最差延遲
+    pub fn evaluate_avb_objectives(&self, avb: usize, solution: &Solution, latest: &Solution) -> Objectives {
         let flowtable = solution.flowtable();
         let latest = latest.selection(avb).current();
         let current = solution.selection(avb).next();
-        let wcd = self.evaluate_avb_wcd(avb, solution);
+        let rejected = solution.outcome(avb).is_unschedulable();
+        let wcd = match rejected {
+            true  => 0,
+            false => self.evaluate_avb_wcd(avb, solution),
+        };
         let max = flowtable.avb_spec(avb).deadline;
 
-        let mut objs = [0.0; 4];
-        objs[0] = 0.0;
-        objs[1] = (wcd > max) as usize as f64;
-        objs[2] = is_rerouted(latest, current) as usize as f64;
-        objs[3] = wcd as f64;
+        let mut objs = Objectives::new();
+        objs.set(Objective::AVBFailed, (wcd > max) as usize as f64);
+        objs.set(Objective::AVBRejected, rejected as usize as f64);
+        objs.set(Objective::Rerouted, is_rerouted(latest, current) as usize as f64);
+        objs.set(Objective::AVBWCD, wcd as f64);
         objs
     }
+    /// 計算原有的四個目標及被拒絕的 AVB 資料流數，以及其它有啟用的目標
     pub fn evaluate_objectives(&self, solution: &Solution, latest: &Solution)
-        -> [f64; 4] {
+        -> Objectives {
         let flowtable = solution.flowtable();
         let mut all_rerouted_count = 0;
         let mut tsn_failed_count = 0;
         let mut avb_failed_count = 0;
+        let mut avb_rejected_count = 0;
         let mut avb_wcd_sum = 0.0;
 
         for nth in flowtable.backgrounds() {
@@ -48,54 +246,277 @@ impl Evaluator {
             all_rerouted_count += is_rerouted(latest, current) as usize;
         }
         for &tsn in flowtable.tsns() {
-            tsn_failed_count += solution.outcome(tsn).is_unschedulable() as usize;
+            tsn_failed_count += self.evaluate_tsn_failure(tsn, solution) as usize;
         }
         for &avb in flowtable.avbs() {
+            if solution.outcome(avb).is_unschedulable() {
+                avb_rejected_count += 1;
+                continue;
+            }
             let wcd = self.evaluate_avb_wcd(avb, solution);
             let max = flowtable.avb_spec(avb).deadline;
             avb_failed_count += (wcd > max) as usize;
             avb_wcd_sum += wcd as f64;
         }
 
-        let mut objs = [0.0; 4];
-        objs[0] = tsn_failed_count as f64;
-        objs[1] = avb_failed_count as f64;
-        objs[2] = all_rerouted_count as f64;
-        objs[3] = avb_wcd_sum;
+        let mut objs = Objectives::new();
+        objs.set(Objective::TSNFailed, tsn_failed_count as f64);
+        objs.set(Objective::AVBFailed, avb_failed_count as f64);
+        objs.set(Objective::AVBRejected, avb_rejected_count as f64);
+        objs.set(Objective::Rerouted, all_rerouted_count as f64);
+        objs.set(Objective::AVBWCD, avb_wcd_sum);
+        if self.is_enabled(Objective::MaxLinkUtilization) {
+            let utilization = self.evaluate_max_link_utilization(solution);
+            objs.set(Objective::MaxLinkUtilization, utilization);
+        }
+        if self.is_enabled(Objective::UtilizationVariance) {
+            let variance = self.evaluate_utilization_variance(solution);
+            objs.set(Objective::UtilizationVariance, variance);
+        }
+        if self.is_enabled(Objective::TSNLatency) {
+            let latency: u32 = flowtable.tsns().iter()
+                .map(|&tsn| self.evaluate_tsn_latency(tsn, solution))
+                .sum();
+            objs.set(Objective::TSNLatency, latency as f64);
+        }
+        if self.is_enabled(Objective::GCLEntries) {
+            let entries = self.count_gcl_entries(solution);
+            objs.set(Objective::GCLEntries, entries as f64);
+        }
         objs
     }
     pub fn evaluate_cost_objectives(&self, solution: &Solution, latest: &Solution)
-        -> (f64, [f64; 4]) {
+        -> (f64, Objectives) {
         let objs = self.evaluate_objectives(solution, latest);
-        let cost = objs.iter()
-            .zip(self.weights.iter())
-            .map(|(x, y)| x * y)
+        let cost = self.weights.iter()
+            .map(|&(obj, weight)| objs.get(obj) * weight)
             .sum();
         (cost, objs)
     }
 
+    /// 只有部份資料流決定路徑時，任何完整解的成本下界，供分支界限法剪枝
+    /// * `fixed` - 編號小於此值的資料流已決定路徑，其餘的可選任一條候選路徑
+    ///
+    /// 失敗數及其它目標皆以 0 計算。其它資料流及 GCL 只會增加延遲，因此 AVB 資料流的最差延遲
+    /// 以不受干擾時的延遲計算，但不超過被拒絕的成本；CBS 的 idle slope 會隨其它資料流變大，
+    /// 因此改用 `bound_cbs_from_below`。
+    pub fn evaluate_cost_lower_bound(&self, solution: &Solution, latest: &Solution, fixed: usize) -> f64 {
+        let flowtable = solution.flowtable();
+        let mut rerouted_count = 0;
+        for nth in flowtable.backgrounds().take_while(|&nth| nth < fixed) {
+            let latest = latest.selection(nth).current();
+            let current = solution.selection(nth).next();
+            rerouted_count += is_rerouted(latest, current) as usize;
+        }
+        let mut bound = rerouted_count as f64 * self.weight_of(Objective::Rerouted);
+        for &avb in flowtable.avbs() {
+            let wcd = match avb < fixed {
+                true  => {
+                    let kth = solution.selection(avb).next().unwrap();
+                    self.analyze_isolated_avb_wcd_for_kth(avb, kth, solution)
+                }
+                false => (0..flowtable.count_candidates(avb))
+                    .map(|kth| self.analyze_isolated_avb_wcd_for_kth(avb, kth, solution))
+                    .min()
+                    .unwrap_or(0),
+            };
+            let cost = wcd as f64 * self.weight_of(Objective::AVBWCD);
+            bound += f64::min(cost, self.weight_of(Objective::AVBRejected));
+        }
+        bound
+    }
+    fn weight_of(&self, objective: Objective) -> f64 {
+        self.weights.iter()
+            .find(|&&(obj, _)| obj == objective)
+            .map_or(0.0, |&(_, weight)| weight)
+    }
+
+    /// 重新計算路徑經過變動連線的 AVB 資料流之最差延遲並存入快取，其餘沿用快取
+    ///
+    /// AVB 資料流的最差延遲只和其路徑上各連線承載的資料流及 GCL 有關，因此只需重算這些資料流。
+    /// 網路演算中交叉流量的突發量會沿路累積，任一連線的變動都可能影響所有資料流，因此全部重算。
+    /// 快取不記錄分析方式，同一個 `Solution` 應只由同一個 `Evaluator` 更新。
+    pub fn update_avb_wcds(&self, solution: &mut Solution) {
+        if self.analysis == Analysis::NC && solution.is_any_dirty() {
+            solution.mark_all_dirty();
+        }
+        let flowtable = solution.flowtable();
+        let mut arrivals = None;
+        for &avb in flowtable.avbs() {
+            let kth = solution.selection(avb).next().unwrap();
+            if solution.cached_avb_wcd(avb, kth).is_some() { continue; }
+            let wcd = match self.analysis {
+                Analysis::NC => {
+                    let arrivals = arrivals.get_or_insert_with(|| self.propagate_avb_arrivals(solution));
+                    self.analyze_avb_wcd_by_nc(avb, kth, solution, arrivals)
+                }
+                _ => self.analyze_avb_wcd_for_kth(avb, kth, solution),
+            };
+            solution.cache_avb_wcd(avb, kth, wcd);
+        }
+        solution.clear_dirty();
+    }
+    /// 計算 AVB 資料流選用第 `kth` 條路徑時的最差延遲，快取仍有效時直接回傳
+    pub fn evaluate_avb_wcd_for_kth(&self, avb: usize, kth: usize, solution: &Solution) -> u32 {
+        if self.analysis == Analysis::NC && solution.is_any_dirty() {
+            return self.analyze_avb_wcd_for_kth(avb, kth, solution);
+        }
+        solution.cached_avb_wcd(avb, kth)
+            .unwrap_or_else(|| self.analyze_avb_wcd_for_kth(avb, kth, solution))
+    }
     /// 計算 AVB 資料流的端對端延遲（包含 TT、BE 及其它 AVB 所造成的延遲）
     /// * `g` - 全局網路拓撲，每條邊上記錄其承載哪些資料流
     /// * `flow` - 該 AVB 資料流的詳細資訊
     /// * `route` - 該 AVB 資料流的路徑
     /// * `flow_table` - 資料流表。需注意的是，這裡僅用了資料流本身的資料，而未使用其隨附資訊
     /// * `gcl` - 所有 TT 資料流的 Gate Control List
-    pub fn evaluate_avb_wcd_for_kth(&self, avb: usize, kth: usize, solution: &Solution) -> u32 {
+    fn analyze_avb_wcd_for_kth(&self, avb: usize, kth: usize, solution: &Solution) -> u32 {
+        let flowtable = solution.flowtable();
+        let network = solution.network();
+        let route = flowtable.candidate(avb, kth);
+        let gcl = &solution.allocated_tsns;
+        let mut end_to_end = 0.0;
+        match self.analysis {
+            Analysis::Legacy => for &edge in route {
+                let traversed_avbs = solution.traversed_avbs.iter(edge);
+                let shaped_tsns = solution.shaped_tsns.iter(edge);
+                let mut per_hop = 0.0;
+                per_hop += transmit_avb_itself(edge, avb, &flowtable, &network);
+                per_hop += interfere_from_be(edge, &network);
+                per_hop += interfere_from_avb(edge, avb, traversed_avbs, &flowtable, &network);
+                per_hop += interfere_from_ats(edge, shaped_tsns, &flowtable, &network);
+                per_hop += interfere_from_tsn(edge, per_hop, gcl);
+                end_to_end += per_hop;
+            },
+            Analysis::CBS => for &edge in route {
+                let traversed_avbs = solution.traversed_avbs.iter(edge);
+                let shaped_tsns = solution.shaped_tsns.iter(edge);
+                let mut per_hop = 0.0;
+                per_hop += analyze_cbs(edge, avb, traversed_avbs, &flowtable, &network);
+                per_hop += interfere_from_ats(edge, shaped_tsns, &flowtable, &network);
+                per_hop += interfere_from_closed_gates(edge, per_hop, gcl);
+                end_to_end += per_hop;
+            },
+            Analysis::NC => {
+                let arrivals = self.propagate_avb_arrivals(solution);
+                return self.analyze_avb_wcd_by_nc(avb, kth, solution, &arrivals);
+            }
+        }
+        end_to_end as u32 // saturated to u32::MAX if unstable
+    }
+    /// 以網路演算計算 AVB 資料流選用第 `kth` 條路徑時的最差延遲
+    /// * `arrivals` - 已接受的 AVB 資料流在其路徑上每一跳的到達曲線，見 `propagate_avb_arrivals`
+    fn analyze_avb_wcd_by_nc(&self, avb: usize, kth: usize, solution: &Solution,
+        arrivals: &[Vec<ArrivalCurve>]) -> u32 {
+        let flowtable = solution.flowtable();
+        let network = solution.network();
+        let gcl = &solution.allocated_tsns;
+        let betas: Vec<_> = flowtable.candidate(avb, kth).iter()
+            .map(|&edge| {
+                let traversed_avbs = solution.traversed_avbs.iter(edge);
+                let shaped_tsns = solution.shaped_tsns.iter(edge);
+                let link = serve_under_gates(edge, shaped_tsns, &flowtable, &network, gcl);
+                let cross = cross_avbs_on(edge, avb, solution, &flowtable, arrivals);
+                serve_avb_by_nc(edge, avb, traversed_avbs, &link, &cross, &flowtable, &network)
+            })
+            .collect();
+        bound_avb_by_nc(avb, &betas, &flowtable)
+    }
+    /// 將已接受的 AVB 資料流之到達曲線逐跳傳遞，每一跳的輸出曲線為 α ⊘ β
+    ///
+    /// 每一跳的服務曲線又取決於交叉流量在該跳的到達曲線，因此從來源端的曲線開始反覆計算，
+    /// 直到突發量不再增加為止；有環狀相依時，收斂的結果即為固定點上的上界。
+    /// 不收斂時以無限大的突發量表示，之後的延遲都會飽和為 `u32::MAX`。
+    /// 回傳值以資料流編號索引，未接受的資料流為空。
+    fn propagate_avb_arrivals(&self, solution: &Solution) -> Vec<Vec<ArrivalCurve>> {
+        let flowtable = solution.flowtable();
+        let network = solution.network();
+        let gcl = &solution.allocated_tsns;
+        let mut arrivals = vec![vec![]; flowtable.len()];
+        for &avb in flowtable.avbs() {
+            let route = route_of(avb, solution, &flowtable);
+            if route.first().is_none_or(|&edge| !solution.traversed_avbs.contains(edge, avb)) {
+                continue;
+            }
+            let spec = flowtable.avb_spec(avb);
+            arrivals[avb] = vec![ArrivalCurve::periodic(spec.size, spec.period); route.len()];
+        }
+        let links: Vec<_> = (0..network.edge_count())
+            .map(|ix| {
+                let edge = ix.into();
+                let shaped_tsns = solution.shaped_tsns.iter(edge);
+                serve_under_gates(edge, shaped_tsns, &flowtable, &network, gcl)
+            })
+            .collect();
+        for _ in 0..MAX_NC_ITERATIONS {
+            let mut next = arrivals.clone();
+            for &avb in flowtable.avbs() {
+                let route = route_of(avb, solution, &flowtable);
+                for hop in 1..arrivals[avb].len() {
+                    let edge = route[hop - 1];
+                    let traversed_avbs = solution.traversed_avbs.iter(edge);
+                    let cross = cross_avbs_on(edge, avb, solution, &flowtable, &arrivals);
+                    let beta = serve_avb_by_nc(edge, avb, traversed_avbs, &links[edge.index()],
+                        &cross, &flowtable, &network);
+                    next[avb][hop] = netcalc::deconvolve(&next[avb][hop - 1], &beta);
+                }
+            }
+            let converged = arrivals.iter().flatten().zip(next.iter().flatten())
+                .all(|(prev, curr)| curr.burst() == prev.burst() || curr.burst() - prev.burst() <= NC_TOLERANCE);
+            arrivals = next;
+            if converged { return arrivals; }
+        }
+        arrivals.iter_mut().flatten()
+            .for_each(|alpha| *alpha = ArrivalCurve::token_bucket(f64::INFINITY, alpha.rate()));
+        arrivals
+    }
+
+    /// 計算 AVB 資料流選用第 `kth` 條路徑、且路徑上沒有其它資料流及 GCL 時的最差延遲
+    fn analyze_isolated_avb_wcd_for_kth(&self, avb: usize, kth: usize, solution: &Solution) -> u32 {
         let flowtable = solution.flowtable();
         let network = solution.network();
         let route = flowtable.candidate(avb, kth);
+        let alone = std::iter::empty();
+        match self.analysis {
+            Analysis::Legacy => route.iter()
+                .map(|&edge| transmit_avb_itself(edge, avb, &flowtable, &network)
+                    + interfere_from_be(edge, &network))
+                .sum::<f64>() as u32,
+            Analysis::CBS => route.iter()
+                .map(|&edge| bound_cbs_from_below(edge, avb, &flowtable, &network))
+                .sum::<f64>() as u32,
+            Analysis::NC => {
+                let betas: Vec<_> = route.iter()
+                    .map(|&edge| {
+                        let link = ServiceCurve::rate_latency(network.bandwidth_on(edge), 0.0);
+                        let cross = ArrivalCurve::zero();
+                        serve_avb_by_nc(edge, avb, alone.clone(), &link, &cross, &flowtable, &network)
+                    })
+                    .collect();
+                bound_avb_by_nc(avb, &betas, &flowtable)
+            }
+        }
+    }
+
+    /// 計算以 ATS 整形的 TSN 資料流之端對端延遲上界（包含 BE、其它 ATS 資料流及 GCL 所造成的延遲）
+    /// * `tsn` - 該 TSN 資料流的編號，其整形方式須為 ATS
+    /// * `kth` - 該 TSN 資料流選用的候選路徑
+    /// * `solution` - 其中 `shaped_tsns` 記錄每條邊上經過哪些 ATS 資料流
+    pub fn evaluate_tsn_ats_bound_for_kth(&self, tsn: usize, kth: usize, solution: &Solution) -> u32 {
+        let flowtable = solution.flowtable();
+        let network = solution.network();
+        let route = flowtable.candidate(tsn, kth);
         let gcl = &solution.allocated_tsns;
         let mut end_to_end = 0.0;
         for &edge in route {
-            let traversed_avbs = solution.traversed_avbs[edge.index()].iter();
+            let shaped_tsns = solution.shaped_tsns.iter(edge);
             let mut per_hop = 0.0;
-            per_hop += transmit_avb_itself(edge, avb, &flowtable, &network);
             per_hop += interfere_from_be(edge, &network);
-            per_hop += interfere_from_avb(edge, avb, traversed_avbs, &flowtable, &network);
+            per_hop += shaped_by_ats(edge, tsn, shaped_tsns, &flowtable, &network);
             per_hop += interfere_from_tsn(edge, per_hop, gcl);
             end_to_end += per_hop;
         }
-        end_to_end as u32
+        end_to_end as u32 // saturated to u32::MAX if unstable
     }
 }
 
@@ -110,19 +531,19 @@ fn transmit_avb_itself(edge: EdgeIndex, avb: usize, flowtable: &FlowTable, netwo
 }
 
 fn interfere_from_be(edge: EdgeIndex, network: &Network) -> f64 {
-    network.duration_on(edge, MTU)
+    network.blocking_on(edge)
 }
 
-// FIXME incomplete implemnetation
+// NOTE 僅為近似分析，保留以便和舊實驗比較；完整的分析請見 `analyze_cbs`
 // "IEEE Standard for Local and metropolitan area networks--Audio Video Bridging (AVB) Systems," in
 // IEEE Std 802.1BA-2011, pp.1-45, 30 Sept. 2011, doi: 10.1109/IEEESTD.2011.6032690.
 
-fn interfere_from_avb<'a, I>(edge: EdgeIndex, avb: usize, others: I,
-    flowtable: &'a FlowTable, network: &Network) -> f64
-    where I: Iterator<Item=&'a usize> {
+fn interfere_from_avb<I>(edge: EdgeIndex, avb: usize, others: I,
+    flowtable: &FlowTable, network: &Network) -> f64
+    where I: Iterator<Item=usize> {
     let mut blocking = 0;
     let spec = flowtable.avb_spec(avb);
-    for &other in others {
+    for other in others {
         if avb == other { continue; }
         let other_spec = flowtable.avb_spec(other);
         if spec.class == 'B' || other_spec.class == 'A' {
@@ -132,7 +553,207 @@ fn interfere_from_avb<'a, I>(edge: EdgeIndex, avb: usize, others: I,
     network.duration_on(edge, blocking) / MAX_AVB_SETTING
 }
 
-// FIXME incomplete implemnetation
+// "IEEE Standard for Local and metropolitan area networks--Audio Video Bridging (AVB) Systems," in
+// IEEE Std 802.1BA-2011, pp.1-45, 30 Sept. 2011, doi: 10.1109/IEEESTD.2011.6032690.
+// J. Cao, P. J. L. Cuijpers, R. J. Bril and J. J. Lukkien, "Independent yet Tight WCRT Analysis for
+// Individual Priority Classes in Ethernet AVB," RTNS '16, pp. 55-64, doi: 10.1145/2997465.2997493.
+
+/// 一條邊上各 SR class 的負載
+/// * `reserved_a`, `reserved_b` - 各 class 的資料流實際保留的頻寬
+/// * `max_frame_a`, `max_frame_b` - 各 class 最大的訊框，超過 MTU 的資料流會被切成數個訊框
+#[derive(Default)]
+struct ClassLoad {
+    reserved_a: f64,
+    reserved_b: f64,
+    max_frame_a: u32,
+    max_frame_b: u32,
+}
+
+/// 統計 `avb` 經過這條邊時各 class 的負載，`others` 中不論是否含有 `avb` 都只計算一次
+fn survey_classes<I>(avb: usize, others: I, flowtable: &FlowTable) -> ClassLoad
+    where I: Iterator<Item=usize> {
+    let mut load = ClassLoad::default();
+    let streams = others.filter(|&other| other != avb).chain(std::iter::once(avb));
+    for other in streams {
+        let other_spec = flowtable.avb_spec(other);
+        let rate = other_spec.size as f64 / other_spec.period as f64;
+        let frame = other_spec.size.min(MTU);
+        match other_spec.class {
+            'A' => {
+                load.reserved_a += rate;
+                load.max_frame_a = max(load.max_frame_a, frame);
+            }
+            _   => {
+                load.reserved_b += rate;
+                load.max_frame_b = max(load.max_frame_b, frame);
+            }
+        }
+    }
+    load
+}
+
+/// 每個 SR class 的 idle slope 即為該 class 的資料流實際保留的頻寬（802.1Qav）
+fn idle_slope_of(class: char, load: &ClassLoad) -> f64 {
+    match class {
+        'A' => load.reserved_a,
+        _   => load.reserved_b,
+    }
+}
+
+/// 信用開始恢復前須等待的時間：低優先權訊框的阻擋，class B 還須等待 class A
+/// 從 hiCredit 一路傳輸到 loCredit
+fn credit_latency(class: char, load: &ClassLoad, edge: EdgeIndex, network: &Network) -> f64 {
+    let bandwidth = network.bandwidth_on(edge);
+    let idle_slope_a = idle_slope_of('A', load);
+    // 低於 class A 的訊框：BE 訊框及 class B 訊框
+    let blocking_a = network.blocking_on(edge).max(network.duration_on(edge, load.max_frame_b));
+    let blocking_b = network.blocking_on(edge);
+    match class {
+        'A' => blocking_a,
+        _   => {
+            let hi_credit_a = blocking_a * idle_slope_a;
+            let monopolized_by_a = match load.max_frame_a {
+                0 => 0.0,
+                _ => hi_credit_a / (bandwidth - idle_slope_a)
+                    + network.duration_on(edge, load.max_frame_a),
+            };
+            blocking_b + monopolized_by_a
+        }
+    }
+}
+
+/// 以 credit-based shaper 分析 AVB 資料流在一條邊上的延遲（不含 TSN 及 ATS 的干擾）
+///
+/// 資料流依 MTU 切成數個訊框，第一個訊框之後的訊框及同 class 其它資料流的訊框
+/// 花費的信用，皆須以 idle slope 恢復。
+fn analyze_cbs<I>(edge: EdgeIndex, avb: usize, others: I,
+    flowtable: &FlowTable, network: &Network) -> f64
+    where I: Iterator<Item=usize> + Clone {
+    let spec = flowtable.avb_spec(avb);
+    let load = survey_classes(avb, others.clone(), flowtable);
+    let idle_slope = idle_slope_of(spec.class, &load);
+    if load.reserved_a + load.reserved_b >= network.bandwidth_on(edge) {
+        return f64::INFINITY;
+    }
+    let same_class: u32 = others
+        .filter(|&other| other != avb && flowtable.avb_spec(other).class == spec.class)
+        .map(|other| flowtable.avb_spec(other).size)
+        .sum();
+    let frame = spec.size.min(MTU);
+    network.duration_on(edge, frame)
+        + (spec.size - frame) as f64 / idle_slope
+        + credit_latency(spec.class, &load, edge, network)
+        + same_class as f64 / idle_slope
+}
+
+/// `analyze_cbs` 在加入任何其它資料流後都不會低於此值
+///
+/// 同 class 的資料流越多，idle slope 越大，剩餘訊框等待信用的時間反而越短；
+/// idle slope 不會超過連線頻寬，因此以連線頻寬恢復剩餘訊框的信用。
+fn bound_cbs_from_below(edge: EdgeIndex, avb: usize, flowtable: &FlowTable, network: &Network) -> f64 {
+    let spec = flowtable.avb_spec(avb);
+    let load = survey_classes(avb, std::iter::empty(), flowtable);
+    let frame = spec.size.min(MTU);
+    network.duration_on(edge, frame)
+        + network.duration_on(edge, spec.size - frame)
+        + credit_latency(spec.class, &load, edge, network)
+}
+
+/// 連線扣除 GCL 關閉事件及 ATS 資料流後，剩給 AVB 及 BE 的服務曲線
+fn serve_under_gates<I>(edge: EdgeIndex, shaped: I,
+    flowtable: &FlowTable, network: &Network, gcl: &GateCtrlList) -> ServiceCurve
+    where I: Iterator<Item=usize> {
+    let events = gcl.get_gate_events(edge);
+    let cycle = max(gcl.hyperperiod(), events.last().map_or(0, |evt| evt.end));
+    let ats = shaped
+        .map(|tsn| flowtable.tsn_spec(tsn))
+        .map(|spec| ArrivalCurve::periodic(spec.size, spec.period))
+        .fold(ArrivalCurve::zero(), |acc, alpha| acc.aggregate(&alpha));
+    ServiceCurve::tas_residual(network.bandwidth_on(edge), &events, cycle)
+        .residual(&ats)
+}
+
+/// 以網路演算分析 AVB 資料流在一條邊上獲得的服務曲線，同 class 的其它資料流以盲多工扣除
+/// * `others` - 經過此連線的 AVB 資料流，用來決定各 class 保留的 idle slope
+/// * `cross` - 同 class 的其它資料流在此連線上的到達曲線
+fn serve_avb_by_nc<I>(edge: EdgeIndex, avb: usize, others: I, link: &ServiceCurve,
+    cross: &ArrivalCurve, flowtable: &FlowTable, network: &Network) -> ServiceCurve
+    where I: Iterator<Item=usize> {
+    let spec = flowtable.avb_spec(avb);
+    let bandwidth = network.bandwidth_on(edge);
+    let load = survey_classes(avb, others, flowtable);
+    let idle_slope = idle_slope_of(spec.class, &load);
+    if idle_slope <= 0.0 {
+        return ServiceCurve::rate_latency(0.0, f64::INFINITY);
+    }
+    ServiceCurve::cbs(idle_slope, credit_latency(spec.class, &load, edge, network))
+        .under_gates(link, bandwidth)
+        .residual(cross)
+}
+
+/// 同 class 的其它 AVB 資料流在連線上匯集的到達曲線，取其抵達該連線時（已傳遞過上游）的曲線
+///
+/// ATS 資料流在每一跳都會被重新整形，因此 `serve_under_gates` 仍以來源端的曲線扣除。
+fn cross_avbs_on(edge: EdgeIndex, avb: usize, solution: &Solution,
+    flowtable: &FlowTable, arrivals: &[Vec<ArrivalCurve>]) -> ArrivalCurve {
+    let class = flowtable.avb_spec(avb).class;
+    solution.traversed_avbs.iter(edge)
+        .filter(|&other| other != avb && flowtable.avb_spec(other).class == class)
+        .map(|other| {
+            let hop = route_of(other, solution, flowtable).iter().position(|&e| e == edge);
+            match hop.and_then(|hop| arrivals[other].get(hop)) {
+                Some(alpha) => alpha.clone(),
+                None        => {
+                    let spec = flowtable.avb_spec(other);
+                    ArrivalCurve::periodic(spec.size, spec.period)
+                }
+            }
+        })
+        .fold(ArrivalCurve::zero(), |acc, alpha| acc.aggregate(&alpha))
+}
+
+fn bound_avb_by_nc(avb: usize, betas: &[ServiceCurve], flowtable: &FlowTable) -> u32 {
+    let spec = flowtable.avb_spec(avb);
+    let alpha = ArrivalCurve::periodic(spec.size, spec.period);
+    let per_hop = netcalc::per_hop_bound(&alpha, betas);
+    let end_to_end = netcalc::end_to_end_bound(&alpha, betas);
+    f64::min(per_hop, end_to_end) as u32 // saturated to u32::MAX if unstable
+}
+
+#[inline]
+fn route_of<'a>(nth: usize, solution: &Solution, flowtable: &'a FlowTable) -> &'a Path {
+    flowtable.candidate(nth, solution.selection(nth).next().unwrap())
+}
+
+// J. Specht and S. Samii, "Urgency-Based Scheduler for Time-Sensitive Switched Ethernet Networks,"
+// 2016 28th Euromicro Conference on Real-Time Systems (ECRTS), 2016, pp. 75-85,
+// doi: 10.1109/ECRTS.2016.27.
+
+fn shaped_by_ats<I>(edge: EdgeIndex, tsn: usize, others: I,
+    flowtable: &FlowTable, network: &Network) -> f64
+    where I: Iterator<Item=usize> {
+    let mut port = PortRegulator::new();
+    port.admit(&TokenBucket::from_spec(flowtable.tsn_spec(tsn)));
+    for other in others {
+        if tsn == other { continue; }
+        port.admit(&TokenBucket::from_spec(flowtable.tsn_spec(other)));
+    }
+    match port.is_stable(network.bandwidth_on(edge)) {
+        true  => network.duration_on(edge, port.burst),
+        false => f64::INFINITY,
+    }
+}
+
+fn interfere_from_ats<I>(edge: EdgeIndex, others: I,
+    flowtable: &FlowTable, network: &Network) -> f64
+    where I: Iterator<Item=usize> {
+    let blocking = others
+        .map(|other| flowtable.tsn_spec(other).size)
+        .sum();
+    network.duration_on(edge, blocking)
+}
+
+// NOTE 僅為近似分析，不循環 GCL；完整的分析請見 `interfere_from_closed_gates`
 // Sune Mølgaard Laursen, Paul Pop, and Wilfried Steiner. 2016. Routing optimization of AVB streams
 // in TSN networks. SIGBED Rev. 13, 4 (September 2016), 43–48.
 // DOI:https://doi.org/10.1145/3015037.3015044
@@ -160,12 +781,47 @@ fn interfere_from_tsn(edge: EdgeIndex, wcd: f64, gcl: &GateCtrlList) -> f64 {
     max_interfere as f64
 }
 
+// L. Zhao, P. Pop, Z. Zheng and Q. Li, "Timing Analysis of AVB Traffic in TSN Networks Using Network
+// Calculus," 2018 IEEE RTAS, pp. 25-36, doi: 10.1109/RTAS.2018.00009.
+
+/// 計算 AVB 資料流需要 `wcd` 的開啟時間時，最多會碰上多少 GCL 關閉的時間
+///
+/// 關閉期間 CBS 的信用會凍結，因此只需把關閉時間加上去。GCL 視為循環，
+/// 最差的情況發生在資料流剛好遇上某個關閉事件開始的時候。
+fn interfere_from_closed_gates(edge: EdgeIndex, wcd: f64, gcl: &GateCtrlList) -> f64 {
+    let events = gcl.get_gate_events(edge);
+    if events.is_empty() { return 0.0; }
+    if !wcd.is_finite() { return f64::INFINITY; }
+    let cycle = max(gcl.hyperperiod(), events.last().unwrap().end) as f64;
+    let closed: u32 = events.iter().map(|evt| evt.end - evt.start).sum();
+    if closed as f64 >= cycle { return f64::INFINITY; }
+
+    let mut max_interfere = 0.0;
+    for i in 0..events.len() {
+        let mut interfere = 0.0;
+        let mut remained = wcd;
+        let mut j = i;
+        loop {
+            let curr = &events[j % events.len()];
+            let next = &events[(j + 1) % events.len()];
+            interfere += (curr.end - curr.start) as f64;
+            let wrapped = if (j + 1) % events.len() == 0 { cycle } else { 0.0 };
+            let opened = next.start as f64 + wrapped - curr.end as f64;
+            if opened >= remained { break; }
+            remained -= opened;
+            j += 1;
+        }
+        max_interfere = f64::max(max_interfere, interfere);
+    }
+    max_interfere
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cnc::CNC;
     use crate::network::Network;
-    use crate::utils::stream::AVB;
+    use crate::utils::stream::{AVB, Shaper, TSN};
     use crate::utils::yaml;
 
     fn setup() -> CNC {
@@ -200,9 +856,243 @@ mod tests {
         let flowtable = solution.flowtable();
         let network = solution.network();
         cnc.scheduler.configure(&mut solution);
-        assert_eq!(interfere_from_avb(edge, 0, [0, 1, 2].iter(), &flowtable, &network), 2.0);
-        assert_eq!(interfere_from_avb(edge, 1, [0, 1, 2].iter(), &flowtable, &network), 1.0);
-        assert_eq!(interfere_from_avb(edge, 2, [0, 1, 2].iter(), &flowtable, &network), 3.0);
+        assert_eq!(interfere_from_avb(edge, 0, 0..3, &flowtable, &network), 2.0);
+        assert_eq!(interfere_from_avb(edge, 1, 0..3, &flowtable, &network), 1.0);
+        assert_eq!(interfere_from_avb(edge, 2, 0..3, &flowtable, &network), 3.0);
+    }
+
+    #[test]
+    fn it_analyzes_cbs() {
+        let cnc = setup();
+        let edge = 0.into();
+        let flowtable = cnc.solution.flowtable();
+        let network = cnc.solution.network();
+        // 1500 B blocking takes 15μs; class A reserves 0.0225 B/μs and recovers credit at that rate
+        assert_eq!(analyze_cbs(edge, 0, 0..3, &flowtable, &network) as u32, 6682);
+        assert_eq!(analyze_cbs(edge, 1, 0..3, &flowtable, &network) as u32, 3349);
+        // class B waits for class A to drain hiCredit of 0.3375 B and send a 150 B frame
+        assert_eq!(analyze_cbs(edge, 2, 0..3, &flowtable, &network) as u32, 17);
+        // a 4500 B stream is split into three frames, the last two wait for credit
+        let mut flowtable = FlowTable::new();
+        flowtable.append(vec![], vec![AVB::new(0, 2, 4500, 10000, 200, 'A')]);
+        let frames = analyze_cbs(edge, 0, 0..1, &flowtable, &network);
+        assert_eq!(frames, 15.0 + 3000.0 / 0.45 + 15.0);
+    }
+
+    #[test]
+    fn it_evaluates_enabled_objectives() {
+        let cnc = setup();
+        let weights = vec![
+            (Objective::MaxLinkUtilization, 1.0),
+            (Objective::UtilizationVariance, 1.0),
+            (Objective::GCLEntries, 1.0),
+        ];
+        let evaluator = Evaluator::new(weights, Analysis::Legacy);
+        let mut solution = cnc.solution.clone();
+        cnc.scheduler.configure(&mut solution);
+        let objs = evaluator.evaluate_objectives(&solution, &cnc.solution);
+        // 300 bytes every 10000μs on a 100 bytes/μs link
+        assert!((objs.get(Objective::MaxLinkUtilization) - 0.0003).abs() < 1e-9);
+        // two of the four directed links are loaded
+        assert!((objs.get(Objective::UtilizationVariance) - 0.00015f64.powi(2)).abs() < 1e-12);
+        assert_eq!(objs.get(Objective::GCLEntries), 0.0);
+        assert_eq!(objs.get(Objective::TSNLatency), 0.0);
+        assert!(!evaluator.is_enabled(Objective::TSNLatency));
+    }
+
+    #[test]
+    fn it_accepts_legacy_weights() {
+        let mut network = Network::new();
+        network.add_nodes(2, 0);
+        network.add_edges(vec![(0, 1, 100.0)]);
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.objectives.clear();
+        config.weights = Some([1000.0, 100.0, 1.0, 1.0]);
+        let cnc = CNC::new(network, config);
+        assert!(Objective::LEGACY.iter().all(|&obj| cnc.evaluator.is_enabled(obj)));
+        assert_eq!(cnc.evaluator.weight_of(Objective::TSNFailed), 1000.0);
+        assert_eq!(cnc.evaluator.weight_of(Objective::AVBWCD), 1.0);
+        assert_eq!(cnc.evaluator.weight_of(Objective::AVBRejected), 100.0);
+    }
+
+    #[test]
+    fn it_costs_more_to_reject_than_to_admit() {
+        let cost = |legacy: bool, share: f64| {
+            let mut network = Network::new();
+            network.add_nodes(2, 0);
+            network.add_edges(vec![(0, 1, 100.0)]);
+            let mut config = yaml::load_config("data/config/default.yaml");
+            config.objectives.remove("avb_rejected");
+            if legacy {
+                config.objectives.clear();
+                config.weights = Some([1000.0, 100.0, 1.0, 1.0]);
+            }
+            config.avb_shares = vec![('A', share), ('B', share)].into_iter().collect();
+            let mut cnc = CNC::new(network, config);
+            cnc.add_streams(vec![], vec![AVB::new(0, 1, 5000, 100, 1000, 'A')]);
+            let mut solution = cnc.solution.clone();
+            cnc.scheduler.configure(&mut solution);
+            cnc.evaluator.update_avb_wcds(&mut solution);
+            cnc.evaluator.evaluate_cost_objectives(&solution, &cnc.solution).0
+        };
+        // 50 bytes/μs fits within 75% of the link but not within 10%
+        assert!(cost(true, 0.1) > cost(true, 0.75));
+        assert!(cost(false, 0.1) > cost(false, 0.75));
+    }
+
+    #[test]
+    fn it_evaluates_wcd_by_network_calculus() {
+        let cnc = setup();
+        let evaluator = Evaluator::new(vec![], Analysis::NC);
+        let mut solution = cnc.solution.clone();
+        cnc.scheduler.configure(&mut solution);
+        // per hop: 15μs blocking, served at the reserved 0.0225 B/μs after the other class A stream,
+        // whose burst has grown after the first hop
+        assert_eq!(evaluator.evaluate_avb_wcd(0, &solution), 60135);
+    }
+
+    #[test]
+    fn it_propagates_output_curves_downstream() {
+        let cnc = setup();
+        let evaluator = Evaluator::new(vec![], Analysis::NC);
+        let mut solution = cnc.solution.clone();
+        cnc.scheduler.configure(&mut solution);
+        let arrivals = evaluator.propagate_avb_arrivals(&solution);
+        for hops in &arrivals {
+            assert_eq!(hops.len(), 2);
+            assert_eq!(hops[1].rate(), hops[0].rate());
+            assert!(hops[1].burst() > hops[0].burst());
+        }
+        // the cross traffic on the second hop is larger than at its source
+        let sources: Vec<_> = arrivals.iter().map(|hops| vec![hops[0].clone(); 2]).collect();
+        let propagated = evaluator.analyze_avb_wcd_by_nc(0, 0, &solution, &arrivals);
+        assert!(propagated > evaluator.analyze_avb_wcd_by_nc(0, 0, &solution, &sources));
+    }
+
+    #[test]
+    fn it_bounds_cost_from_below() {
+        let cnc = setup();
+        let weights = vec![(Objective::AVBWCD, 1.0), (Objective::AVBRejected, 100.0)];
+        for &analysis in &[Analysis::Legacy, Analysis::CBS, Analysis::NC] {
+            let evaluator = Evaluator::new(weights.clone(), analysis);
+            let mut solution = cnc.solution.clone();
+            cnc.scheduler.configure(&mut solution);
+            for avb in 0..3 {
+                let isolated = evaluator.analyze_isolated_avb_wcd_for_kth(avb, 0, &solution);
+                assert!(isolated <= evaluator.evaluate_avb_wcd(avb, &solution));
+            }
+            let (cost, _) = evaluator.evaluate_cost_objectives(&solution, &cnc.solution);
+            let bound = evaluator.evaluate_cost_lower_bound(&solution, &cnc.solution, 0);
+            assert!(bound > 0.0 && bound <= cost);
+        }
+    }
+
+    #[test]
+    fn it_bounds_multi_frame_avbs_from_below() {
+        let mut network = Network::new();
+        network.add_nodes(2, 0);
+        network.add_edges(vec![(0, 1, 100.0)]);
+        // the second stream raises the class A idle slope from 0.45 to 30.45 B/μs
+        let avbs = vec![
+            AVB::new(0, 1, 4500, 10000, 20000, 'A'),
+            AVB::new(0, 1, 300, 10, 20000, 'A'),
+        ];
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.analysis = String::from("cbs");
+        config.objectives.insert(String::from("avb_rejected"), 1e6);
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(vec![], avbs);
+        let mut solution = cnc.solution.clone();
+        cnc.scheduler.configure(&mut solution);
+        cnc.evaluator.update_avb_wcds(&mut solution);
+        assert!(solution.outcome(0).is_schedulable() && solution.outcome(1).is_schedulable());
+        let isolated = cnc.evaluator.analyze_isolated_avb_wcd_for_kth(0, 0, &solution);
+        assert!(isolated <= cnc.evaluator.evaluate_avb_wcd(0, &solution));
+        let (cost, _) = cnc.evaluator.evaluate_cost_objectives(&solution, &cnc.solution);
+        let bound = cnc.evaluator.evaluate_cost_lower_bound(&solution, &cnc.solution, 2);
+        assert!(bound <= cost);
+    }
+
+    #[test]
+    fn it_updates_wcd_of_affected_avbs() {
+        let mut network = Network::new();
+        network.add_nodes(3, 0);
+        network.add_edges(vec![(0, 1, 100.0), (1, 2, 100.0)]);
+        let avbs = vec![
+            AVB::new(0, 2, 75, 10000, 200, 'A'),
+            AVB::new(0, 1, 75, 10000, 200, 'A'),
+        ];
+        let config = yaml::load_config("data/config/default.yaml");
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(vec![], avbs);
+        let mut solution = cnc.solution.clone();
+        cnc.scheduler.configure(&mut solution);
+        cnc.evaluator.update_avb_wcds(&mut solution);
+        let wcd = cnc.evaluator.analyze_avb_wcd_for_kth(0, 0, &solution);
+        assert_eq!(solution.cached_avb_wcd(0, 0), Some(wcd));
+        // only the first stream traverses the link from node 1 to node 2
+        solution.mark_dirty(2.into());
+        assert_eq!(solution.cached_avb_wcd(0, 0), None);
+        assert!(solution.cached_avb_wcd(1, 0).is_some());
+        cnc.evaluator.update_avb_wcds(&mut solution);
+        assert_eq!(solution.cached_avb_wcd(0, 0), Some(wcd));
+    }
+
+    #[test]
+    fn it_evaluates_ats_bound() {
+        let mut network = Network::new();
+        network.add_nodes(3, 0);
+        network.add_edges(vec![(0, 1, 100.0), (1, 2, 100.0)]);
+        let shaped = |size, deadline| TSN {
+            shaper: Shaper::ATS, ..TSN::new(0, 2, size, 1000, deadline, 0)
+        };
+        let tsns = vec![
+            shaped(300, 100),
+            shaped(500, 40),
+            TSN { jitter: Some(39), ..shaped(300, 100) },
+        ];
+        let config = yaml::load_config("data/config/default.yaml");
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(tsns, vec![]);
+        let mut solution = cnc.solution.clone();
+        cnc.scheduler.configure(&mut solution);
+        // per hop: 15.0 blocked by BE + 11.0 by bursts of all streams
+        assert_eq!(cnc.evaluator.evaluate_tsn_ats_bound(0, &solution), 52);
+        assert!(!cnc.evaluator.evaluate_tsn_failure(0, &solution));
+        assert!(cnc.evaluator.evaluate_tsn_failure(1, &solution));
+        // best case: 3μs transmission on each hop
+        let timing = Timing { latency: 52, jitter: 46, slack: 48 };
+        assert_eq!(cnc.evaluator.evaluate_tsn_timing(0, &solution), Some(timing));
+        // within its deadline, but jitters more than allowed
+        assert!(cnc.evaluator.evaluate_tsn_failure(2, &solution));
+    }
+
+    #[test]
+    fn it_evaluates_tsn_timing() {
+        let mut network = Network::new();
+        network.add_nodes(2, 0);
+        network.add_edges(vec![(0, 1, 100.0)]);
+        let tsns = vec![
+            TSN::new(0, 1, 1500, 100, 100, 0),
+            TSN { jitter: Some(0), ..TSN::new(0, 1, 3000, 100, 100, 10) },
+        ];
+        let config = yaml::load_config("data/config/default.yaml");
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(tsns, vec![]);
+        let mut solution = cnc.solution.clone();
+        solution.allocated_tsns = GateCtrlList::new(&cnc.network, 200);
+        cnc.scheduler.configure(&mut solution);
+        // two instances: 0..15 and 100..115
+        let timing = Timing { latency: 15, jitter: 0, slack: 85 };
+        assert_eq!(cnc.evaluator.evaluate_tsn_timing(0, &solution), Some(timing));
+        // released at 10, but waits for the link until 15
+        let timing = Timing { latency: 35, jitter: 0, slack: 65 };
+        assert_eq!(cnc.evaluator.evaluate_tsn_timing(1, &solution), Some(timing));
+        assert!(!cnc.evaluator.evaluate_tsn_failure(1, &solution));
+        // gated for 30μs and 60μs in a hyperperiod of 200μs
+        let utilizations = cnc.evaluator.evaluate_link_utilizations(&solution);
+        assert!((utilizations[0].tsn - 0.45).abs() < 1e-9);
+        assert_eq!(utilizations[1].total(), 0.0);
     }
 
     #[test]
@@ -40,6 +40,10 @@ impl FlowTable {
     pub fn is_empty(&self) -> bool {
         self.streams.len() == 0
     }
+    pub fn is_avb(&self, nth: usize) -> bool {
+        debug_assert!(nth < self.streams.len());
+        matches!(self.streams[nth], Either::AVB(..))
+    }
     pub fn tsn_spec(&self, nth: usize) -> &TSN {
         debug_assert!(nth < self.streams.len());
         debug_assert!(matches!(self.streams[nth], Either::TSN(..)));
@@ -80,6 +84,9 @@ impl FlowTable {
         for nth in self.inputs() {
             let (src, dst) = self.ends(nth);
             let candidates = algorithm.candidates(src, dst);
+            if candidates.is_empty() {
+                panic!("Failed route a stream between disconnected end devices");
+            }
             self.candidates.push(candidates.clone());
         }
     }
@@ -88,6 +95,10 @@ impl FlowTable {
         debug_assert!(kth < self.candidates[nth].len());
         &self.candidates[nth][kth]
     }
+    pub fn count_candidates(&self, nth: usize) -> usize {
+        debug_assert!(nth < self.candidates.len());
+        self.candidates[nth].len()
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,5 @@
 use std::ops::Range;
+use std::sync::Arc;
 
 use num::integer::lcm;
 
@@ -21,30 +22,64 @@ impl Entry {
     }
 }
 
+/// 佇列的隔離模式，決定不同 TSN 資料流能否同時待在同一個佇列中
+/// * `Legacy` - 原本的規則：封包進入佇列時不得有其它資料流的封包，同一資料流相鄰封包的佇列事件會合併；
+///   只在封包進入佇列時檢查，保留以便和舊實驗比較
+/// * `Stream` - Craciunas 等人所稱的 stream isolation：一條資料流的實例從第一個封包進入佇列，
+///   到最後一個封包離開為止，都不得有其它資料流的封包，即各資料流依序使用佇列
+/// * `Frame` - Craciunas 等人所稱的 frame isolation：封包待在佇列的整段期間都不得有其它資料流的封包，
+///   不同資料流的封包仍可在不同時間交錯使用同一佇列
+/// * `Shared` - 不做隔離，只要求佇列中同時存在的封包數不超過給定深度
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Isolation {
+    #[default]
+    Legacy,
+    Stream,
+    Frame,
+    Shared(usize),
+}
+
+// S. S. Craciunas, R. S. Oliver, M. Chmelík, and W. Steiner, "Scheduling Real-Time Communication in
+// IEEE 802.1Qbv Time Sensitive Networks," in Proceedings of the 24th International Conference on
+// Real-Time Networks and Systems (RTNS '16), 2016, pp. 183-192, doi: 10.1145/2997465.2997470.
+
 #[derive(Clone, Debug, Default)]
 struct Event {
     stream: usize,
     window: Range<u32>,
 }
 
+/// 每個連線及佇列的事件各自以 `Arc` 共享，複製時只增加參考計數，直到修改時才複製該份事件
 #[derive(Clone, Debug, Default)]
 pub struct GateCtrlList {
     hyperperiod: u32,
-    events: Vec<Vec<Event>>,
+    events: Vec<Arc<Vec<Event>>>,
+    guard_bands: Arc<[u32]>,
 }
 
 impl GateCtrlList {
     pub fn new(network: &Network, hyperperiod: u32) -> Self {
         let edge_count = network.edge_count();
-        let events = vec![vec![]; edge_count * 9];
-        Self { hyperperiod, events }
+        let events = (0..edge_count * 9).map(|_| Arc::new(vec![])).collect();
+        let guard_bands = vec![0; edge_count].into();
+        Self { hyperperiod, events, guard_bands }
+    }
+    /// 在每條連線保留 guard band，長度為低優先權封包最多能造成的阻塞時間：
+    /// 一般連線為一個 MTU，啟用 frame preemption 的連線只需一個不可搶占的片段
+    pub fn reserve_guard_bands(&mut self, network: &Network) {
+        self.guard_bands = (0..network.edge_count())
+            .map(|ix| network.blocking_on(ix.into()).ceil() as u32)
+            .collect::<Vec<_>>()
+            .into();
     }
     // XXX this function have never been called
     pub fn update_hyperperiod(&mut self, new_p: u32) {
         self.hyperperiod = lcm(self.hyperperiod, new_p);
     }
     pub fn clear(&mut self) {
-        self.events.iter_mut().for_each(|e| e.clear());
+        self.events.iter_mut()
+            .filter(|evts| !evts.is_empty())
+            .for_each(|evts| *evts = Arc::new(vec![]));
     }
     pub fn hyperperiod(&self) -> u32 {
         self.hyperperiod
@@ -52,8 +87,9 @@ impl GateCtrlList {
     fn events(&self, entry: Entry) -> &Vec<Event> {
         &self.events[entry.index()]
     }
+    /// 若這份事件仍與其它 GCL 共享，會先複製一份再修改
     fn events_mut(&mut self, entry: Entry) -> &mut Vec<Event> {
-        &mut self.events[entry.index()]
+        Arc::make_mut(&mut self.events[entry.index()])
     }
     /// 回傳 `link_id` 上所有閘門關閉事件。
     /// * `回傳值` - 一個陣列，其內容為 (事件開始時間, 事件結束時間);
@@ -79,6 +115,19 @@ impl GateCtrlList {
         }
         lookup
     }
+    /// 回傳 `link_id` 上屬於 `tsn` 這條資料流的閘門事件
+    pub fn get_stream_windows(&self, edge: EdgeIndex, tsn: usize) -> Vec<Range<u32>> {
+        self.events(Entry::Port(edge)).iter()
+            .filter(|event| event.stream == tsn)
+            .map(|event| event.window.clone())
+            .collect()
+    }
+    /// 閘門關閉後到 TSN 封包開始傳輸前的 guard band，由排程器保留在每個窗口的開頭
+    ///
+    /// 未呼叫 `reserve_guard_bands` 時為 0，如同以往由 BE 的阻塞時間涵蓋。
+    pub fn guard_band(&self, edge: EdgeIndex) -> u32 {
+        self.guard_bands[edge.index()]
+    }
     pub fn insert_gate_evt(
         &mut self,
         edge: EdgeIndex,
@@ -111,6 +160,7 @@ impl GateCtrlList {
         que: u8,
         tsn: usize,
         window: Range<u32>,
+        isolation: Isolation,
     ) {
         if window.start == window.end { return; }
         let event = Event::new(tsn, window);
@@ -123,7 +173,8 @@ impl GateCtrlList {
                 edge, que, event
             ),
             Err(pos) => {
-                if pos > 0 && evts[pos - 1].window.end >= event.window.start {
+                let overlapped = pos > 0 && evts[pos - 1].window.end >= event.window.start;
+                if overlapped && isolation == Isolation::Legacy {
                     // FIXME don't extend event, just panic
                     // 開始時間位於前一個事件中，則延伸前一個事件
                     evts[pos - 1].window.end = event.window.end;
@@ -166,47 +217,80 @@ impl GateCtrlList {
         }
         (self.hyperperiod, true)
     }
-    /// 回傳 None 者，代表當前即是空的
+    /// 回傳 None 者，代表當前即是空的（或在不隔離時，仍有空間可以容納一個封包）
     pub fn get_next_queue_empty_time(
         &self,
         edge: EdgeIndex,
         queue_id: u8,
         time: u32,
+        isolation: Isolation,
     ) -> Option<u32> {
         let queue = Entry::Queue(edge, queue_id);
         let evts = self.events(queue);
-        for event in evts {
-            if event.window.start <= time {
-                if event.window.end > time {
-                    return Some(event.window.end);
-                } else {
-                    return None;
+        if isolation == Isolation::Legacy {
+            for event in evts {
+                if event.window.start <= time {
+                    if event.window.end > time {
+                        return Some(event.window.end);
+                    } else {
+                        return None;
+                    }
                 }
             }
+            return None;
+        }
+        let mut ends = evts.iter()
+            .filter(|event| event.window.contains(&time))
+            .map(|event| event.window.end)
+            .collect::<Vec<_>>();
+        match isolation {
+            Isolation::Legacy => unreachable!(),
+            Isolation::Stream | Isolation::Frame => ends.into_iter().max(),
+            Isolation::Shared(depth) if ends.len() < depth => None,
+            Isolation::Shared(depth) => {
+                // 等到足夠多的封包離開佇列，使剩下的封包數少於深度
+                ends.sort_unstable();
+                Some(ends[ends.len() - depth])
+            }
         }
-        None
     }
-    pub fn remove(&mut self, edge: EdgeIndex, tsn: usize) {
-        let port = Entry::Port(edge);
-        let gate_evt = self.events_mut(port);
-        let mut i = 0;
-        while i < gate_evt.len() {
-            if gate_evt[i].stream == tsn {
-                gate_evt.remove(i);
-            } else {
-                i += 1;
+    /// 確認封包在 `window` 這段期間待在佇列中，是否符合隔離模式的限制
+    ///
+    /// `Stream` 模式下 `window` 應從這個實例的第一個封包進入佇列時算起。
+    pub fn is_queue_vacant(
+        &self,
+        edge: EdgeIndex,
+        queue_id: u8,
+        window: Range<u32>,
+        isolation: Isolation,
+    ) -> bool {
+        let queue = Entry::Queue(edge, queue_id);
+        let evts = self.events(queue);
+        let overlapped = evts.iter()
+            .filter(|event| event.window.start < window.end
+                && window.start < event.window.end);
+        match isolation {
+            Isolation::Legacy => true,
+            Isolation::Stream | Isolation::Frame => overlapped.count() == 0,
+            Isolation::Shared(depth) => {
+                // 佇列深度只會在某個封包進入時增加，因此只需檢查這些時間點
+                let overlapped = overlapped.collect::<Vec<_>>();
+                let instants = overlapped.iter()
+                    .map(|event| event.window.start.max(window.start));
+                instants.map(|time| overlapped.iter()
+                        .filter(|event| event.window.contains(&time))
+                        .count())
+                    .all(|occupied| occupied < depth)
             }
         }
-        for queue_id in 0..MAX_QUEUE {
-            let queue = Entry::Queue(edge, queue_id);
-            let queue_evt = self.events_mut(queue);
-            let mut i = 0;
-            while i < queue_evt.len() {
-                if queue_evt[i].stream == tsn {
-                    queue_evt.remove(i);
-                } else {
-                    i += 1;
-                }
+    }
+    pub fn remove(&mut self, edge: EdgeIndex, tsn: usize) {
+        let entries = std::iter::once(Entry::Port(edge))
+            .chain((0..MAX_QUEUE).map(|queue_id| Entry::Queue(edge, queue_id)));
+        for entry in entries {
+            // 沒有這條資料流的事件就不修改，以免複製仍在共享的事件
+            if self.events(entry).iter().any(|event| event.stream == tsn) {
+                self.events_mut(entry).retain(|event| event.stream != tsn);
             }
         }
     }
@@ -220,3 +304,42 @@ impl Event {
         (self.window.start, self.window.end, self.stream)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> GateCtrlList {
+        let mut network = Network::new();
+        network.add_nodes(2, 0);
+        network.add_edges(vec![(0, 1, 100.0)]);
+        GateCtrlList::new(&network, 100)
+    }
+
+    #[test]
+    fn it_isolates_queue_by_frame() {
+        let mut gcl = setup();
+        let edge = 0.into();
+        let isolation = Isolation::Frame;
+        gcl.insert_queue_evt(edge, 0, 1, 10..20, isolation);
+        gcl.insert_queue_evt(edge, 0, 2, 15..30, isolation);
+        assert_eq!(gcl.get_next_queue_empty_time(edge, 0, 5, isolation), None);
+        assert_eq!(gcl.get_next_queue_empty_time(edge, 0, 16, isolation), Some(30));
+        assert!(gcl.is_queue_vacant(edge, 0, 0..10, isolation));
+        assert!(!gcl.is_queue_vacant(edge, 0, 0..11, isolation));
+    }
+
+    #[test]
+    fn it_limits_shared_queue_depth() {
+        let mut gcl = setup();
+        let edge = 0.into();
+        let isolation = Isolation::Shared(2);
+        gcl.insert_queue_evt(edge, 0, 1, 10..20, isolation);
+        gcl.insert_queue_evt(edge, 0, 2, 15..30, isolation);
+        assert_eq!(gcl.get_next_queue_empty_time(edge, 0, 12, isolation), None);
+        assert_eq!(gcl.get_next_queue_empty_time(edge, 0, 16, isolation), Some(20));
+        assert!(gcl.is_queue_vacant(edge, 0, 0..12, isolation));
+        assert!(!gcl.is_queue_vacant(edge, 0, 0..16, isolation));
+        assert!(gcl.is_queue_vacant(edge, 0, 20..40, isolation));
+    }
+}
@@ -1,10 +1,17 @@
+mod ats;
 mod flowtable;
 mod gcl;
+mod objective;
+mod occupancy;
 mod solution;
 
 pub mod evaluator;
+pub mod netcalc;
 
-pub use evaluator::Evaluator;
+pub use ats::{PortRegulator, TokenBucket};
+pub use evaluator::{Analysis, Evaluator, Timing, Utilization};
 pub use flowtable::FlowTable;
-pub use gcl::GateCtrlList;
+pub use gcl::{GateCtrlList, Isolation};
+pub use objective::{Objective, Objectives};
+pub use occupancy::Occupancy;
 pub use solution::Solution;
@@ -0,0 +1,230 @@
+use std::ops::Range;
+
+// J.-Y. Le Boudec and P. Thiran, "Network Calculus: A Theory of Deterministic Queuing Systems for
+// the Internet," Springer-Verlag, LNCS 2050, 2001.
+// 時間單位為微秒，資料量單位為位元組，速率單位為 位元組 / 微秒
+
+/// 凹的到達曲線，表示為數條仿射函數 `burst + rate * t` 的最小值（t > 0）
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArrivalCurve {
+    pieces: Vec<(f64, f64)>,
+}
+
+/// 速率-延遲服務曲線 `rate * max(t - latency, 0)`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ServiceCurve {
+    pub rate: f64,
+    pub latency: f64,
+}
+
+impl ArrivalCurve {
+    pub fn zero() -> Self {
+        ArrivalCurve::token_bucket(0.0, 0.0)
+    }
+    pub fn token_bucket(burst: f64, rate: f64) -> Self {
+        ArrivalCurve { pieces: vec![(burst, rate)] }
+    }
+    /// 週期性資料流的階梯函數 `size * ceil(t / period)`，以其凹包絡表示
+    pub fn periodic(size: u32, period: u32) -> Self {
+        ArrivalCurve::token_bucket(size as f64, size as f64 / period as f64)
+    }
+    pub fn burst(&self) -> f64 {
+        self.pieces.iter().map(|&(b, _)| b).fold(f64::INFINITY, f64::min)
+    }
+    pub fn rate(&self) -> f64 {
+        self.pieces.iter().map(|&(_, r)| r).fold(f64::INFINITY, f64::min)
+    }
+    pub fn at(&self, t: f64) -> f64 {
+        self.pieces.iter().map(|&(b, r)| b + r * t).fold(f64::INFINITY, f64::min)
+    }
+    /// 多條資料流匯集後的到達曲線：兩個最小值相加，等於兩兩相加後再取最小值
+    pub fn aggregate(&self, other: &ArrivalCurve) -> Self {
+        let pieces = self.pieces.iter()
+            .flat_map(|&(b1, r1)| other.pieces.iter().map(move |&(b2, r2)| (b1 + b2, r1 + r2)))
+            .collect();
+        ArrivalCurve { pieces: prune(pieces) }
+    }
+    /// 長期速率那一段的突發量
+    fn burst_at_rate(&self) -> f64 {
+        let rate = self.rate();
+        self.pieces.iter()
+            .filter(|&&(_, r)| r == rate)
+            .map(|&(b, _)| b)
+            .fold(f64::INFINITY, f64::min)
+    }
+    /// 曲線上各段的交點，凹函數的最大偏差必定發生在這些點或原點
+    fn breakpoints(&self) -> Vec<f64> {
+        let mut points = vec![0.0];
+        for (i, &(b1, r1)) in self.pieces.iter().enumerate() {
+            for &(b2, r2) in self.pieces.iter().skip(i + 1) {
+                if r1 == r2 { continue; }
+                let t = (b2 - b1) / (r1 - r2);
+                if t > 0.0 { points.push(t); }
+            }
+        }
+        points
+    }
+}
+
+impl ServiceCurve {
+    pub fn rate_latency(rate: f64, latency: f64) -> Self {
+        ServiceCurve { rate, latency }
+    }
+    /// Credit-based shaper 的服務曲線，以 idle slope 為保證速率
+    /// * `latency` - 信用恢復前需等待的時間，包含低優先權的阻擋及較高 class 的傳輸
+    pub fn cbs(idle_slope: f64, latency: f64) -> Self {
+        ServiceCurve::rate_latency(idle_slope, latency)
+    }
+    /// 連線扣除 GCL 關閉事件後剩下的服務
+    /// * `events` - 循環 GCL 中所有關閉的時段，須已排序且不重疊
+    /// * `cycle` - GCL 循環的長度
+    ///
+    /// 保證速率為開啟時間的比例，延遲則取所有從關閉事件開始的區間中，最差的落後量。
+    pub fn tas_residual(bandwidth: f64, events: &[Range<u32>], cycle: u32) -> Self {
+        if events.is_empty() {
+            return ServiceCurve::rate_latency(bandwidth, 0.0);
+        }
+        let closed: u32 = events.iter().map(|evt| evt.end - evt.start).sum();
+        if closed >= cycle {
+            return ServiceCurve::rate_latency(0.0, f64::INFINITY);
+        }
+        let opened_ratio = (cycle - closed) as f64 / cycle as f64;
+        let mut latency = 0.0;
+        for i in 0..events.len() {
+            let start = events[i].start as f64;
+            let mut wrapped = 0.0;
+            let mut closed_acc = 0.0;
+            for k in 0..events.len() {
+                let j = (i + k) % events.len();
+                if k > 0 && j == 0 { wrapped += cycle as f64; }
+                closed_acc += (events[j].end - events[j].start) as f64;
+                let length = events[j].end as f64 + wrapped - start;
+                let opened = length - closed_acc;
+                latency = f64::max(latency, length - opened / opened_ratio);
+            }
+        }
+        ServiceCurve::rate_latency(bandwidth * opened_ratio, latency)
+    }
+    /// 在 GCL 關閉期間 CBS 的信用會凍結，故只在開啟的比例下服務
+    pub fn under_gates(&self, link: &ServiceCurve, bandwidth: f64) -> Self {
+        ServiceCurve::rate_latency(self.rate * link.rate / bandwidth, self.latency + link.latency)
+    }
+    /// 盲多工（blind multiplexing）下，扣除交叉流量後剩下的服務
+    pub fn residual(&self, cross: &ArrivalCurve) -> Self {
+        let (burst, rate) = (cross.burst_at_rate(), cross.rate());
+        if rate >= self.rate {
+            return ServiceCurve::rate_latency(0.0, f64::INFINITY);
+        }
+        let latency = (self.rate * self.latency + burst) / (self.rate - rate);
+        ServiceCurve::rate_latency(self.rate - rate, latency)
+    }
+    /// 串接的兩個伺服器：速率取小者，延遲相加
+    pub fn convolve(&self, other: &ServiceCurve) -> Self {
+        ServiceCurve::rate_latency(f64::min(self.rate, other.rate), self.latency + other.latency)
+    }
+}
+
+/// 去除被其它仿射函數完全蓋過的部份
+fn prune(pieces: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    let dominated = |&(b1, r1): &(f64, f64), i: usize| {
+        pieces.iter().enumerate().any(|(j, &(b2, r2))| {
+            j != i && b2 <= b1 && r2 <= r1 && (b2 < b1 || r2 < r1 || j < i)
+        })
+    };
+    pieces.iter()
+        .enumerate()
+        .filter(|&(i, piece)| !dominated(piece, i))
+        .map(|(_, &piece)| piece)
+        .collect()
+}
+
+/// 最小加法反卷積 α ⊘ β，即資料流離開伺服器後的到達曲線
+///
+/// 速率超過服務速率的那幾段會被略過；少了這些段的最小值只會更大，因此結果仍是上界。
+pub fn deconvolve(alpha: &ArrivalCurve, beta: &ServiceCurve) -> ArrivalCurve {
+    let pieces: Vec<_> = alpha.pieces.iter()
+        .filter(|&&(_, r)| r <= beta.rate)
+        .map(|&(b, r)| (b + r * beta.latency, r))
+        .collect();
+    match pieces.is_empty() {
+        true  => ArrivalCurve::token_bucket(f64::INFINITY, alpha.rate()),
+        false => ArrivalCurve { pieces },
+    }
+}
+
+/// 延遲上界，即到達曲線與服務曲線的最大水平距離
+pub fn delay_bound(alpha: &ArrivalCurve, beta: &ServiceCurve) -> f64 {
+    if beta.rate <= 0.0 || alpha.rate() > beta.rate {
+        return f64::INFINITY;
+    }
+    alpha.breakpoints().into_iter()
+        .map(|t| beta.latency + alpha.at(t) / beta.rate - t)
+        .fold(beta.latency, f64::max)
+}
+
+/// 佇列長度上界，即到達曲線與服務曲線的最大垂直距離
+pub fn backlog_bound(alpha: &ArrivalCurve, beta: &ServiceCurve) -> f64 {
+    if beta.rate <= 0.0 || alpha.rate() > beta.rate {
+        return f64::INFINITY;
+    }
+    let mut points = alpha.breakpoints();
+    points.push(beta.latency);
+    points.into_iter()
+        .map(|t| alpha.at(t) - beta.rate * f64::max(t - beta.latency, 0.0))
+        .fold(0.0, f64::max)
+}
+
+/// 逐跳累加的端對端延遲上界，每一跳都以反卷積更新資料流的到達曲線
+pub fn per_hop_bound(alpha: &ArrivalCurve, betas: &[ServiceCurve]) -> f64 {
+    let mut alpha = alpha.clone();
+    let mut bound = 0.0;
+    for beta in betas {
+        bound += delay_bound(&alpha, beta);
+        alpha = deconvolve(&alpha, beta);
+    }
+    bound
+}
+
+/// 先將整條路徑的服務曲線卷積起來再計算延遲，突發量只需付一次（pay-bursts-only-once）
+pub fn end_to_end_bound(alpha: &ArrivalCurve, betas: &[ServiceCurve]) -> f64 {
+    let beta = betas.iter()
+        .fold(ServiceCurve::rate_latency(f64::INFINITY, 0.0), |acc, beta| acc.convolve(beta));
+    delay_bound(alpha, &beta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_bounds_token_bucket_by_rate_latency() {
+        let alpha = ArrivalCurve::token_bucket(100.0, 1.0);
+        let beta = ServiceCurve::rate_latency(10.0, 5.0);
+        assert_eq!(delay_bound(&alpha, &beta), 15.0);
+        assert_eq!(backlog_bound(&alpha, &beta), 105.0);
+        assert_eq!(deconvolve(&alpha, &beta), ArrivalCurve::token_bucket(105.0, 1.0));
+        let residual = beta.residual(&ArrivalCurve::token_bucket(20.0, 2.0));
+        assert_eq!(residual, ServiceCurve::rate_latency(8.0, 8.75));
+    }
+
+    #[test]
+    fn it_pays_bursts_only_once() {
+        let alpha = ArrivalCurve::token_bucket(100.0, 1.0);
+        let betas = [ServiceCurve::rate_latency(10.0, 5.0); 2];
+        // per hop: (5 + 100 / 10) + (5 + 105 / 10)
+        assert_eq!(per_hop_bound(&alpha, &betas), 30.5);
+        // end to end: 10 + 100 / 10
+        assert_eq!(end_to_end_bound(&alpha, &betas), 20.0);
+    }
+
+    #[test]
+    fn it_derives_tas_residual() {
+        // GCL: X - - X X X X X X -
+        let beta = ServiceCurve::tas_residual(100.0, &[0..1, 3..9], 10);
+        assert_eq!(beta.rate, 30.0);
+        // worst case starts at the second closure: 6μs closed before any service
+        assert_eq!(beta.latency, 6.0);
+        let alpha = ArrivalCurve::periodic(100, 50).aggregate(&ArrivalCurve::periodic(50, 50));
+        assert_eq!(alpha, ArrivalCurve::token_bucket(150.0, 3.0));
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+/// 可由設定檔啟用並加權的最佳化目標
+/// * `TSNFailed` - 無法排入 GCL 或超過延遲上界的 TSN 資料流數
+/// * `AVBFailed` - 最差延遲超過 deadline 的 AVB 資料流數
+/// * `AVBRejected` - 因超過保留頻寬上限而被拒絕的 AVB 資料流數
+/// * `Rerouted` - 與上一次配置相比，更換路徑的資料流數
+/// * `AVBWCD` - 所有 AVB 資料流最差延遲的總和
+/// * `MaxLinkUtilization` - 所有連線中，最高的頻寬使用率
+/// * `UtilizationVariance` - 所有連線頻寬使用率的變異數，越小代表負載越平均
+/// * `TSNLatency` - 所有 TSN 資料流端對端延遲的總和
+/// * `GCLEntries` - 所有連線上 GCL 的事件數
+///
+/// 這是固定的目標集合，不提供外掛或註冊新目標的機制，設定檔只能從中選用並加權。
+/// 新增一個目標必須修改原始碼：在此列舉加入一項，並同時修改 `ALL`、`name` 及
+/// `Evaluator::evaluate_objectives` 中的 match。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Objective {
+    TSNFailed,
+    AVBFailed,
+    AVBRejected,
+    Rerouted,
+    AVBWCD,
+    MaxLinkUtilization,
+    UtilizationVariance,
+    TSNLatency,
+    GCLEntries,
+}
+
+impl Objective {
+    pub const ALL: [Objective; 9] = [
+        Objective::TSNFailed, Objective::AVBFailed, Objective::AVBRejected, Objective::Rerouted,
+        Objective::AVBWCD, Objective::MaxLinkUtilization, Objective::UtilizationVariance,
+        Objective::TSNLatency, Objective::GCLEntries,
+    ];
+    /// 舊版設定檔 `weights: [f64; 4]` 中各權重依序對應的目標；`AVBRejected` 由 `CNC::new` 比照 `AVBFailed` 補上
+    pub const LEGACY: [Objective; 4] = [
+        Objective::TSNFailed, Objective::AVBFailed, Objective::Rerouted, Objective::AVBWCD,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            Objective::TSNFailed           => "tsn_failed",
+            Objective::AVBFailed           => "avb_failed",
+            Objective::AVBRejected         => "avb_rejected",
+            Objective::Rerouted            => "rerouted",
+            Objective::AVBWCD              => "avb_wcd",
+            Objective::MaxLinkUtilization  => "max_link_utilization",
+            Objective::UtilizationVariance => "utilization_variance",
+            Objective::TSNLatency          => "tsn_latency",
+            Objective::GCLEntries          => "gcl_entries",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Objective::ALL.iter().cloned().find(|obj| obj.name() == name)
+    }
+}
+
+/// 依名稱記錄的一組目標值
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Objectives {
+    values: Vec<(Objective, f64)>,
+}
+
+impl Objectives {
+    pub fn new() -> Self {
+        Objectives { values: vec![] }
+    }
+    /// 沒有計算過的目標視為 0
+    pub fn get(&self, objective: Objective) -> f64 {
+        self.values.iter()
+            .find(|&&(obj, _)| obj == objective)
+            .map_or(0.0, |&(_, value)| value)
+    }
+    pub fn set(&mut self, objective: Objective, value: f64) {
+        match self.values.iter_mut().find(|(obj, _)| *obj == objective) {
+            Some((_, old)) => *old = value,
+            None => {
+                self.values.push((objective, value));
+                self.values.sort_by_key(|&(obj, _)| obj);
+            }
+        }
+    }
+    pub fn iter(&self) -> impl Iterator<Item=&(Objective, f64)> {
+        self.values.iter()
+    }
+}
+
+impl fmt::Display for Objectives {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let entries = self.values.iter()
+            .map(|(obj, value)| format!("{}: {:.2}", obj.name(), value))
+            .collect::<Vec<_>>();
+        write!(f, "{{{}}}", entries.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_objectives_by_name() {
+        let mut objs = Objectives::new();
+        objs.set(Objective::AVBWCD, 12.0);
+        objs.set(Objective::TSNFailed, 1.0);
+        objs.set(Objective::AVBWCD, 34.5);
+        assert_eq!(objs.get(Objective::AVBWCD), 34.5);
+        assert_eq!(objs.get(Objective::GCLEntries), 0.0);
+        assert_eq!(format!("{}", objs), "{tsn_failed: 1.00, avb_wcd: 34.50}");
+        assert_eq!(Objective::from_name("max_link_utilization"), Some(Objective::MaxLinkUtilization));
+        assert_eq!(Objective::from_name("unknown"), None);
+    }
+}
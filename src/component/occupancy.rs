@@ -0,0 +1,97 @@
+use crate::network::EdgeIndex;
+
+const WORD_BITS: usize = 64;
+
+/// 記錄每條連線上經過哪些資料流的位元集合
+///
+/// 所有連線共用一塊連續的記憶體，每條連線佔 `stride` 個字組，因此複製時只需一次配置。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Occupancy {
+    words: Vec<u64>,
+    stride: usize,
+    edge_count: usize,
+}
+
+impl Occupancy {
+    pub fn new(edge_count: usize) -> Self {
+        Occupancy { words: vec![], stride: 0, edge_count }
+    }
+    /// 確保可以容納編號小於 `len` 的資料流，已記錄的內容不變
+    pub fn resize(&mut self, len: usize) {
+        let stride = len.div_ceil(WORD_BITS);
+        if stride <= self.stride { return; }
+        let mut words = vec![0; stride * self.edge_count];
+        for (ix, chunk) in self.words.chunks(self.stride.max(1)).enumerate() {
+            words[ix * stride..ix * stride + chunk.len()].copy_from_slice(chunk);
+        }
+        self.words = words;
+        self.stride = stride;
+    }
+    pub fn insert(&mut self, edge: EdgeIndex, stream: usize) {
+        self.resize(stream + 1);
+        let (word, bit) = self.locate(edge, stream);
+        self.words[word] |= 1 << bit;
+    }
+    pub fn remove(&mut self, edge: EdgeIndex, stream: usize) {
+        if stream >= self.stride * WORD_BITS { return; }
+        let (word, bit) = self.locate(edge, stream);
+        self.words[word] &= !(1 << bit);
+    }
+    pub fn contains(&self, edge: EdgeIndex, stream: usize) -> bool {
+        if stream >= self.stride * WORD_BITS { return false; }
+        let (word, bit) = self.locate(edge, stream);
+        self.words[word] >> bit & 1 == 1
+    }
+    /// 依編號由小到大列出連線上的資料流
+    pub fn iter(&self, edge: EdgeIndex) -> Ones<'_> {
+        debug_assert!(edge.index() < self.edge_count);
+        let start = edge.index() * self.stride;
+        Ones { words: &self.words[start..start + self.stride], next: 0, word: 0 }
+    }
+    fn locate(&self, edge: EdgeIndex, stream: usize) -> (usize, usize) {
+        debug_assert!(edge.index() < self.edge_count);
+        (edge.index() * self.stride + stream / WORD_BITS, stream % WORD_BITS)
+    }
+}
+
+/// 逐一取出字組中為 1 的位元，每次以 `trailing_zeros` 跳到下一個
+#[derive(Clone)]
+pub struct Ones<'a> {
+    words: &'a [u64],
+    next: usize,
+    word: u64,
+}
+
+impl Iterator for Ones<'_> {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        while self.word == 0 {
+            self.word = *self.words.get(self.next)?;
+            self.next += 1;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some((self.next - 1) * WORD_BITS + bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_streams_when_resized() {
+        let mut occupancy = Occupancy::new(2);
+        occupancy.insert(1.into(), 3);
+        occupancy.insert(0.into(), 70);
+        occupancy.insert(1.into(), 64);
+        occupancy.remove(1.into(), 3);
+        occupancy.remove(0.into(), 200);
+        assert_eq!(occupancy.iter(0.into()).collect::<Vec<_>>(), [70]);
+        assert_eq!(occupancy.iter(1.into()).collect::<Vec<_>>(), [64]);
+        occupancy.resize(150);
+        assert!(occupancy.contains(0.into(), 70));
+        assert!(!occupancy.contains(1.into(), 3));
+        assert_eq!(occupancy.iter(1.into()).collect::<Vec<_>>(), [64]);
+    }
+}
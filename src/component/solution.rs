@@ -1,10 +1,8 @@
-use std::rc::{Rc, Weak};
-
-use hashbrown::HashSet;
+use std::sync::{Arc, Weak};
 
 use super::FlowTable;
-use crate::component::GateCtrlList;
-use crate::network::Network;
+use crate::component::{GateCtrlList, Occupancy};
+use crate::network::{EdgeIndex, Network};
 
 const KTH_DEFAULT: usize = 0;
 
@@ -13,8 +11,12 @@ const KTH_DEFAULT: usize = 0;
 pub struct Solution {
     selections: Vec<Select>,
     outcomes: Vec<Outcome>,
+    offsets: Vec<u32>,
     pub allocated_tsns: GateCtrlList,
-    pub traversed_avbs: Vec<HashSet<usize>>,
+    pub traversed_avbs: Occupancy,
+    pub shaped_tsns: Occupancy,
+    dirty_edges: Vec<bool>,
+    avb_wcds: Vec<Option<(usize, u32)>>,
     pub flowtable: Weak<FlowTable>,
     pub network: Weak<Network>,
 }
@@ -39,16 +41,20 @@ impl Solution {
         Solution {
             selections: vec![],
             outcomes: vec![],
+            offsets: vec![],
             allocated_tsns: GateCtrlList::new(graph, 1),
-            traversed_avbs: vec![HashSet::new(); edge_count],
+            traversed_avbs: Occupancy::new(edge_count),
+            shaped_tsns: Occupancy::new(edge_count),
+            dirty_edges: vec![true; edge_count],
+            avb_wcds: vec![],
             flowtable: Weak::new(),
             network: Weak::new(),
         }
     }
-    pub fn flowtable(&self) -> Rc<FlowTable> {
+    pub fn flowtable(&self) -> Arc<FlowTable> {
         self.flowtable.upgrade().unwrap()
     }
-    pub fn network(&self) -> Rc<Network> {
+    pub fn network(&self) -> Arc<Network> {
         self.network.upgrade().unwrap()
     }
     pub fn select(&mut self, nth: usize, kth: usize) {
@@ -74,9 +80,56 @@ impl Solution {
         debug_assert!(nth < self.outcomes.len());
         self.outcomes[nth] = Outcome::Unschedulable(kth);
     }
+    /// TSN 資料流在其釋出窗口中實際選用的 offset
+    pub fn offset(&self, nth: usize) -> u32 {
+        debug_assert!(nth < self.offsets.len());
+        self.offsets[nth]
+    }
+    pub fn set_offset(&mut self, nth: usize, offset: u32) {
+        debug_assert!(nth < self.offsets.len());
+        self.offsets[nth] = offset;
+    }
     pub fn resize(&mut self, len: usize) {
         self.selections.resize(len, Select::Pending(KTH_DEFAULT));
-        self.outcomes.resize(len, Outcome::Pending)
+        self.outcomes.resize(len, Outcome::Pending);
+        self.offsets.resize(len, 0);
+        self.avb_wcds.resize(len, None);
+        self.traversed_avbs.resize(len);
+        self.shaped_tsns.resize(len);
+    }
+    /// 標記連線上承載的資料流或 GCL 有所變動，經過此連線的 AVB 資料流需重新計算最差延遲
+    pub fn mark_dirty(&mut self, edge: EdgeIndex) {
+        self.dirty_edges[edge.index()] = true;
+    }
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty_edges.iter_mut().for_each(|dirty| *dirty = true);
+    }
+    pub fn is_dirty(&self, edge: EdgeIndex) -> bool {
+        self.dirty_edges[edge.index()]
+    }
+    pub fn is_any_dirty(&self) -> bool {
+        self.dirty_edges.iter().any(|&dirty| dirty)
+    }
+    pub fn clear_dirty(&mut self) {
+        self.dirty_edges.iter_mut().for_each(|dirty| *dirty = false);
+    }
+    /// 快取的 AVB 資料流最差延遲，僅在選用同一條路徑且路徑上沒有變動時有效
+    pub fn cached_avb_wcd(&self, avb: usize, kth: usize) -> Option<u32> {
+        debug_assert!(avb < self.avb_wcds.len());
+        let wcd = match self.avb_wcds[avb] {
+            Some((cached, wcd)) if cached == kth => wcd,
+            _                                    => return None,
+        };
+        let flowtable = self.flowtable();
+        let route = flowtable.candidate(avb, kth);
+        match route.iter().any(|&edge| self.is_dirty(edge)) {
+            true  => None,
+            false => Some(wcd),
+        }
+    }
+    pub fn cache_avb_wcd(&mut self, avb: usize, kth: usize, wcd: u32) {
+        debug_assert!(avb < self.avb_wcds.len());
+        self.avb_wcds[avb] = Some((kth, wcd));
     }
 }
 
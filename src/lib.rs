@@ -3,6 +3,7 @@ pub mod cnc;
 pub mod component;
 pub mod network;
 pub mod scheduler;
+pub mod simulator;
 pub mod utils;
 
 pub const MAX_QUEUE: u8 = 8;
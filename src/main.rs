@@ -12,6 +12,7 @@ Options:
     -c, --config PATH     Configure CNC algorithm and parameters
     -a, --algorithm TYPE  Override algorithm used to calculate routing set
     -m, --memory NUM      Override memory parameters for ACO algorithm
+    -p, --param LIST      Override ACO parameters, e.g. rho=0.3,q0=0.1,m=40
     -s, --seed NUM        Override random seed for ACO or RO algorithm
 ";
 
@@ -30,6 +31,9 @@ fn main() {
         .unwrap_or_else(|| String::from("data/config/default.yaml"));
     let mut config = yaml::load_config(&path);
     config.override_from_args(args);
+    if config.algorithm == "aco" {
+        println!("ACO parameters {}", config.parameters);
+    }
 
     let mut cnc = CNC::new(network, config);
 
@@ -5,3 +5,5 @@ pub use topology::{EdgeIndex, Network, NodeIndex};
 pub type Path = Vec<EdgeIndex>;
 
 pub const MTU: u32 = 1500;
+/// 啟用 frame preemption 後，express 封包最多需等待的不可搶占片段（802.3br，含前導碼等開銷）
+pub const MAX_FRAGMENT: u32 = 143;
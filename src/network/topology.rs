@@ -1,5 +1,7 @@
 use std::iter;
 
+use super::{MAX_FRAGMENT, MTU};
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct NodeIndex(usize);
 
@@ -52,6 +54,7 @@ pub struct Node {
 pub struct Edge {
     ends: (NodeIndex, NodeIndex),
     bandwidth: f64,
+    preemption: bool,
 }
 
 impl Node {
@@ -61,7 +64,7 @@ impl Node {
 }
 impl Edge {
     pub fn new(ends: (NodeIndex, NodeIndex), bandwidth: f64) -> Self {
-        Edge { ends, bandwidth }
+        Edge { ends, bandwidth, preemption: false }
     }
 }
 
@@ -120,6 +123,28 @@ impl Network {
             self.edges.push(Edge::new(ends, bandwidth));
         }
     }
+    pub fn bandwidth_on(&self, edge: EdgeIndex) -> f64 {
+        debug_assert!(edge.index() < self.edges.len());
+        self.edges[edge.index()].bandwidth
+    }
+    pub fn is_preemptable(&self, edge: EdgeIndex) -> bool {
+        debug_assert!(edge.index() < self.edges.len());
+        self.edges[edge.index()].preemption
+    }
+    /// 啟用兩端點間（雙向）所有連線的 frame preemption
+    pub fn enable_preemption(&mut self, end0: usize, end1: usize) {
+        let ends = [(end0.into(), end1.into()), (end1.into(), end0.into())];
+        self.edges.iter_mut()
+            .filter(|edge| ends.contains(&edge.ends))
+            .for_each(|edge| edge.preemption = true);
+    }
+    /// 低優先權封包在此連線上最多能造成的阻塞時間
+    pub fn blocking_on(&self, edge: EdgeIndex) -> f64 {
+        match self.is_preemptable(edge) {
+            true  => self.duration_on(edge, MAX_FRAGMENT),
+            false => self.duration_on(edge, MTU),
+        }
+    }
     pub fn duration_on(&self, edge: EdgeIndex, size: u32) -> f64 {
         debug_assert!(edge.index() < self.edges.len());
         size as f64 / self.edges[edge.index()].bandwidth
@@ -153,4 +178,15 @@ mod tests {
         assert_eq!(network.endpoints(2.into()), &(1.into(), 2.into()));
         assert_eq!(network.endpoints(3.into()), &(2.into(), 1.into()));
     }
+    #[test]
+    fn it_reduces_blocking_by_preemption() {
+        let mut network = Network::default();
+        network.add_nodes(3, 0);
+        network.add_edges(vec![(0, 1, 10.0), (1, 2, 20.0)]);
+        network.enable_preemption(2, 1);
+        assert_eq!(network.blocking_on(0.into()), 150.0);
+        assert_eq!(network.blocking_on(1.into()), 150.0);
+        assert_eq!(network.blocking_on(2.into()), 7.15);
+        assert_eq!(network.blocking_on(3.into()), 7.15);
+    }
 }
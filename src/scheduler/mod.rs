@@ -1,3 +1,3 @@
 mod runtime_reconf;
 
-pub use runtime_reconf::Scheduler;
+pub use runtime_reconf::{OrderBy, Scheduler};
@@ -1,7 +1,12 @@
 use std::cmp::{max, Ordering};
 use std::ops::Range;
 
-use crate::component::{FlowTable, Solution};
+use ordered_float::OrderedFloat;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+
+use crate::component::{FlowTable, Isolation, Solution};
 use crate::network::{EdgeIndex, MTU};
 use crate::utils::stream::TSN;
 use crate::MAX_QUEUE;
@@ -10,15 +15,16 @@ use crate::MAX_QUEUE;
 struct Schedule {
     windows: Vec<Vec<Range<u32>>>, // windows[#hop][#frame]
     queue: u8,
+    offset: u32,
 }
 
 impl Schedule {
-    fn new(route: &[EdgeIndex], size: u32, queue: u8) -> Self {
+    fn new(route: &[EdgeIndex], size: u32, queue: u8, offset: u32) -> Self {
         let route_len = route.len();
         let frame_len = ((size - 1) / MTU + 1) as usize;
         static MAX: Range<u32> = std::u32::MAX..std::u32::MAX;
         let windows = vec![vec![MAX.clone(); frame_len]; route_len];
-        Schedule { windows, queue }
+        Schedule { windows, queue, offset }
     }
     fn shape(&self) -> (usize, usize) {
         debug_assert!(!self.windows.is_empty());
@@ -26,19 +32,49 @@ impl Schedule {
     }
 }
 
+/// 排程 TSN 資料流的先後順序
+/// * `Deadline` - 依 deadline、週期、路徑長排序
+/// * `Laxity` - 扣除傳輸時間後，剩餘時間較少的要排前面
+/// * `Size` - 資料量大的要排前面
+/// * `Utilization` - 佔用頻寬比例（資料量 / 週期）大的要排前面
+/// * `Hops` - 路徑長的要排前面
+/// * `Random` - 以亂數種子打亂順序
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrderBy {
+    #[default]
+    Deadline,
+    Laxity,
+    Size,
+    Utilization,
+    Hops,
+    Random,
+}
+
 #[derive(Default)]
-pub struct Scheduler {}
+pub struct Scheduler {
+    isolation: Isolation,
+    orderings: Vec<OrderBy>,
+    shares: Vec<(char, f64)>,
+    seed: u64,
+}
 
 impl Scheduler {
-    pub fn new() -> Self {
-        Scheduler {}
+    /// * `orderings` - 依序嘗試的排序方式，採用第一個能排入所有資料流者
+    /// * `shares` - 各 SR class 連同更高優先權的 class 最多可保留的頻寬比例，如同 SRP 的 deltaBandwidth
+    pub fn new(isolation: Isolation, orderings: Vec<OrderBy>, shares: Vec<(char, f64)>,
+        seed: u64) -> Self {
+        assert!(!orderings.is_empty(), "至少需要一種 TSN 排序方式");
+        Scheduler { isolation, orderings, shares, seed }
     }
     pub fn configure(&self, solution: &mut Solution) {
         self.configure_avbs(solution);
+        self.configure_shaped_tsns(solution);
         self.configure_tsns(solution);
         solution.confirm();
     }
-    /// 更新 AVB 資料流表與圖上資訊
+    /// 更新 AVB 資料流表與圖上資訊，超過保留頻寬上限的資料流會被拒絕
+    ///
+    /// 先前被拒絕的資料流即使沒有更換路徑，也會再嘗試一次。
     fn configure_avbs(&self, solution: &mut Solution) {
         let flowtable = solution.flowtable();
         let avbs = flowtable.avbs();
@@ -51,55 +87,120 @@ impl Scheduler {
             remove_traversed_avb(solution, avb, kth);
         }
 
+        let is_rejected = |avb: usize| !solution.selection(avb).is_switch()
+            && !solution.outcome(avb).is_schedulable();
         targets.extend(flowtable.avbs().iter()
-            .filter(|&&avb| solution.selection(avb).is_pending()));
+            .filter(|&&avb| solution.selection(avb).is_pending() || is_rejected(avb)));
         for &avb in &targets {
             let kth = solution.selection(avb).next().unwrap();
-            insert_traversed_avb(solution, avb, kth);
+            match self.is_admissible(solution, avb, kth) {
+                true  => {
+                    insert_traversed_avb(solution, avb, kth);
+                    solution.flag_schedulable(avb, kth);
+                }
+                false => solution.flag_unschedulable(avb, kth),
+            }
+        }
+    }
+    /// 確認 AVB 資料流加入後，路徑上每條連線中各 class 的累計保留頻寬都不超過上限
+    fn is_admissible(&self, solution: &Solution, avb: usize, kth: usize) -> bool {
+        let flowtable = solution.flowtable();
+        let network = solution.network();
+        let spec = flowtable.avb_spec(avb);
+        let rate = |size: u32, period: u32| size as f64 / period as f64;
+        flowtable.candidate(avb, kth).iter().all(|&edge| {
+            let bandwidth = network.bandwidth_on(edge);
+            self.shares.iter()
+                .filter(|&&(class, _)| spec.class <= class)
+                .all(|&(class, share)| {
+                    let reserved: f64 = solution.traversed_avbs.iter(edge)
+                        .map(|other| flowtable.avb_spec(other))
+                        .filter(|other| other.class <= class)
+                        .map(|other| rate(other.size, other.period))
+                        .sum();
+                    reserved + rate(spec.size, spec.period) <= share * bandwidth
+                })
+        })
+    }
+    /// 更新 ATS 整形的 TSN 資料流表與圖上資訊，這些資料流不需配置 GCL
+    fn configure_shaped_tsns(&self, solution: &mut Solution) {
+        let flowtable = solution.flowtable();
+        let tsns = flowtable.tsns();
+        let is_shaped = |tsn: &&usize| flowtable.tsn_spec(**tsn).is_shaped();
+        let mut targets = Vec::with_capacity(tsns.len());
+
+        targets.extend(flowtable.tsns().iter().filter(is_shaped)
+            .filter(|&&tsn| solution.selection(tsn).is_switch()));
+        for &tsn in &targets {
+            let kth = solution.selection(tsn).current().unwrap();
+            remove_shaped_tsn(solution, tsn, kth);
+        }
+
+        targets.extend(flowtable.tsns().iter().filter(is_shaped)
+            .filter(|&&tsn| solution.selection(tsn).is_pending()));
+        for &tsn in &targets {
+            let kth = solution.selection(tsn).next().unwrap();
+            let offset = flowtable.tsn_spec(tsn).offset_min;
+            insert_shaped_tsn(solution, tsn, kth);
+            solution.flag_schedulable(tsn, kth);
+            solution.set_offset(tsn, offset);
         }
     }
     /// 更新 TSN 資料流表與 GCL
     fn configure_tsns(&self, solution: &mut Solution) {
         let flowtable = solution.flowtable();
         let tsns = flowtable.tsns();
+        let is_gated = |tsn: &&usize| !flowtable.tsn_spec(**tsn).is_shaped();
         let mut targets = Vec::with_capacity(tsns.len());
 
-        targets.extend(flowtable.tsns().iter()
+        targets.extend(flowtable.tsns().iter().filter(is_gated)
             .filter(|&&tsn| solution.selection(tsn).is_switch()));
         for &tsn in &targets {
             let kth = solution.selection(tsn).current().unwrap();
             remove_allocated_tsn(solution, tsn, kth);
         }
 
-        targets.extend(flowtable.tsns().iter()
+        targets.extend(flowtable.tsns().iter().filter(is_gated)
             .filter(|&&tsn| solution.selection(tsn).is_pending()));
-        let result = self.try_schedule_tsns(solution, targets);
+        let result = self.try_schedule_tsns(solution, targets, self.orderings[0]);
 
         if result.is_ok() { return; }
 
+        targets = tsns.iter().filter(is_gated).cloned().collect();
+        let (&last, orderings) = self.orderings.split_last().unwrap();
+        solution.mark_all_dirty();
+        for &order in orderings {
+            solution.allocated_tsns.clear();
+            let result = self.try_schedule_tsns(solution, targets.clone(), order);
+            if result.is_ok() { return; }
+        }
         solution.allocated_tsns.clear();
-        targets = tsns.clone();
-        self.try_schedule_tsns(solution, targets).unwrap();
+        self.try_schedule_tsns(solution, targets, last).unwrap();
     }
 
     // M. L. Raagaard, P. Pop, M. Gutiérrez and W. Steiner, "Runtime reconfiguration of time-sensitive
     // networking (TSN) schedules for Fog Computing," 2017 IEEE Fog World Congress (FWC), Santa Clara,
     // CA, USA, 2017, pp. 1-6, doi: 10.1109/FWC.2017.8368523.
 
-    fn try_schedule_tsns(&self, solution: &mut Solution, tsns: Vec<usize>)
-        -> Result<(), ()> {
+    fn try_schedule_tsns(&self, solution: &mut Solution, tsns: Vec<usize>,
+        order: OrderBy) -> Result<(), ()> {
         let flowtable = solution.flowtable();
         let mut tsns = tsns;
         tsns.sort_by(|&tsn1, &tsn2|
-            compare_tsn(tsn1, tsn2, solution, &flowtable)
+            compare_tsn(tsn1, tsn2, order, solution, &flowtable)
         );
+        if order == OrderBy::Random {
+            let mut rng = ChaChaRng::seed_from_u64(self.seed);
+            tsns.shuffle(&mut rng);
+        }
         for tsn in tsns {
             let mut queue = 0;
             let kth = solution.selection(tsn).next().unwrap();
             let period = flowtable.tsn_spec(tsn).period;
             loop {
-                if let Ok(schedule) = self.try_calculate_windows(tsn, queue, solution) {
-                    insert_allocated_tsn(solution, tsn, kth, schedule, period);
+                if let Ok(schedule) = self.try_release_windows(tsn, queue, solution) {
+                    solution.set_offset(tsn, schedule.offset);
+                    insert_allocated_tsn(solution, tsn, kth, schedule, period, self.isolation);
                     solution.flag_schedulable(tsn, kth);
                     break;
                 }
@@ -112,7 +213,19 @@ impl Scheduler {
         }
         Ok(())
     }
-    fn try_calculate_windows(&self, tsn: usize, queue: u8,
+    /// 在釋出窗口中由早到晚嘗試每個 offset，直到資料流能被排入為止
+    fn try_release_windows(&self, tsn: usize, queue: u8,
+        solution: &Solution) -> Result<Schedule, ()> {
+        let flowtable = solution.flowtable();
+        let spec = flowtable.tsn_spec(tsn);
+        // deadline 是從 offset 起算的，即使只位移 1μs 也可能讓資料流排得進去
+        for offset in spec.offsets() {
+            let result = self.try_calculate_windows(tsn, queue, offset, solution);
+            if result.is_ok() { return result; }
+        }
+        Err(())
+    }
+    fn try_calculate_windows(&self, tsn: usize, queue: u8, offset: u32,
         solution: &Solution) -> Result<Schedule, ()> {
         let flowtable = solution.flowtable();
         let network = solution.network();
@@ -122,20 +235,21 @@ impl Scheduler {
         let gcl = &solution.allocated_tsns;
         let hyperperiod = gcl.hyperperiod();
 
-        let mut schedule = Schedule::new(route, spec.size, queue);
+        let mut schedule = Schedule::new(route, spec.size, queue, offset);
         let (route_len, frame_len) = schedule.shape();
         let windows = &mut schedule.windows;
 
         for r in 0..route_len {
             let edge = route[r];
-            let transmit_time = network.duration_on(edge, MTU).ceil() as u32;
+            let transmit_time = network.duration_on(edge, MTU).ceil() as u32
+                + gcl.guard_band(edge);
             for f in 0..frame_len {
                 let prev_frame_done = match f {
-                    0 => spec.offset,
+                    0 => offset,
                     _ => windows[r][f - 1].end,
                 };
                 let prev_link_done = match r {
-                    0 => spec.offset,
+                    0 => offset,
                     _ => windows[r - 1][f].end,
                 };
                 let ingress = max(prev_frame_done, prev_link_done);
@@ -146,17 +260,18 @@ impl Scheduler {
                     // 考慮 hyper period 中每種狀況
                     /*
                      * 1. 每個連結一個時間只能傳輸一個封包
-                     * 2. 同個佇列一個時間只能容納一個資料流（但可能容納該資料流的數個封包）
+                     * 2. 同個佇列中不同資料流的隔離方式依 `Isolation` 而定
                      * 3. 要符合 deadline 的需求
                      */
-                    // QUESTION 搞清楚第二點是為什麼？
+                    // NOTE 佇列是 FIFO，若不同資料流的封包在佇列中交錯，一旦某個封包遺失或
+                    // 延遲，其後的封包都會錯過自己的窗口，因此需要隔離或限制佇列深度
                     loop {
                         // NOTE 確認沒有其它封包在這個連線上傳輸
                         let option =
                             gcl.get_next_empty_time(edge, time_shift + egress, transmit_time);
                         if let Some(time) = option {
                             egress = time - time_shift;
-                            assert_within_deadline(egress + transmit_time, spec)?;
+                            assert_within_deadline(egress + transmit_time, offset, spec)?;
                             continue;
                         }
                         // NOTE 確認傳輸到下個地方時，下個連線的佇列是空的（沒有其它的資料流）
@@ -166,18 +281,30 @@ impl Scheduler {
                                 route[r + 1],
                                 queue,
                                 time_shift + (egress + transmit_time),
+                                self.isolation,
                             );
                             if let Some(time) = option {
                                 egress = time - time_shift;
-                                assert_within_deadline(egress + transmit_time, spec)?;
+                                assert_within_deadline(egress + transmit_time, offset, spec)?;
                                 continue;
                             }
                         }
-                        assert_within_deadline(egress + transmit_time, spec)?;
+                        assert_within_deadline(egress + transmit_time, offset, spec)?;
                         break;
                     }
-                    // QUESTION 是否要檢查 arrive_time ~ cur_offset+trans_time 這段時間中
-                    // 有沒有發生同個佇列被佔用的事件？
+                }
+                // NOTE 確認封包待在佇列的這段期間中，沒有違反隔離模式的限制
+                if r > 0 {
+                    let arrival = match self.isolation {
+                        Isolation::Stream => windows[r - 1][0].start,
+                        _                 => windows[r - 1][f].start,
+                    };
+                    for time_shift in (0..hyperperiod).step_by(spec.period as usize) {
+                        let window = (time_shift + arrival)..(time_shift + egress);
+                        if !gcl.is_queue_vacant(edge, queue, window, self.isolation) {
+                            return Err(());
+                        }
+                    }
                 }
                 windows[r][f] = egress..(egress + transmit_time);
             }
@@ -193,26 +320,42 @@ impl Scheduler {
     }
 }
 
-/// 排序的標準：
+/// 排序的標準，依 `order` 決定主要依據，相同者再依序比較：
 /// * `deadline` - 時間較緊的要排前面
 /// * `period` - 週期短的要排前面
 /// * `route length` - 路徑長的要排前面
-fn compare_tsn(tsn1: usize, tsn2: usize,
+fn compare_tsn(tsn1: usize, tsn2: usize, order: OrderBy,
     solution: &Solution, flowtable: &FlowTable) -> Ordering {
     let spec1 = flowtable.tsn_spec(tsn1);
     let spec2 = flowtable.tsn_spec(tsn2);
-    let routelen = |tsn: usize| {
+    let network = solution.network();
+    let route = |tsn: usize| {
         let kth = solution.selection(tsn).next().unwrap();
-        solution.flowtable().candidate(tsn, kth).len()
+        flowtable.candidate(tsn, kth)
+    };
+    let routelen = |tsn: usize| route(tsn).len();
+    let laxity = |tsn: usize, spec: &TSN| {
+        let transmit = network.duration_along(route(tsn), spec.size);
+        OrderedFloat(spec.deadline as f64 - transmit)
     };
-    spec1.deadline.cmp(&spec2.deadline)
+    let utilization = |spec: &TSN| OrderedFloat(spec.size as f64 / spec.period as f64);
+    let primary = match order {
+        OrderBy::Deadline    => Ordering::Equal,
+        OrderBy::Laxity      => laxity(tsn1, spec1).cmp(&laxity(tsn2, spec2)),
+        OrderBy::Size        => spec1.size.cmp(&spec2.size).reverse(),
+        OrderBy::Utilization => utilization(spec1).cmp(&utilization(spec2)).reverse(),
+        OrderBy::Hops        => routelen(tsn1).cmp(&routelen(tsn2)).reverse(),
+        OrderBy::Random      => Ordering::Equal, // shuffled afterwards
+    };
+    primary
+        .then(spec1.deadline.cmp(&spec2.deadline))
         .then(spec1.period.cmp(&spec2.period))
         .then(routelen(tsn1).cmp(&routelen(tsn2)).reverse())
 }
 
-fn assert_within_deadline(delay: u32, spec: &TSN) -> Result<u32, ()> {
-    match delay < spec.offset + spec.deadline {
-        true  => Ok(spec.offset + spec.deadline - delay),
+fn assert_within_deadline(delay: u32, offset: u32, spec: &TSN) -> Result<u32, ()> {
+    match delay < offset + spec.deadline {
+        true  => Ok(offset + spec.deadline - delay),
         false => Err(()),
     }
 }
@@ -220,33 +363,53 @@ fn assert_within_deadline(delay: u32, spec: &TSN) -> Result<u32, ()> {
 fn remove_traversed_avb(solution: &mut Solution, avb: usize, kth: usize) {
     let flowtable = solution.flowtable();
     let route = flowtable.candidate(avb, kth); // kth_route without clone
-    for edge in route {
-        let set = &mut solution.traversed_avbs[edge.index()];
-        set.remove(&avb);
+    for &edge in route {
+        solution.traversed_avbs.remove(edge, avb);
+        solution.mark_dirty(edge);
     }
 }
 
 fn insert_traversed_avb(solution: &mut Solution, avb: usize, kth: usize) {
     let flowtable = solution.flowtable();
     let route = flowtable.candidate(avb, kth); // kth_route without clone
-    for edge in route {
-        let set = &mut solution.traversed_avbs[edge.index()];
-        set.insert(avb);
+    for &edge in route {
+        solution.traversed_avbs.insert(edge, avb);
+        solution.mark_dirty(edge);
+    }
+}
+
+fn remove_shaped_tsn(solution: &mut Solution, tsn: usize, kth: usize) {
+    let flowtable = solution.flowtable();
+    let route = flowtable.candidate(tsn, kth); // kth_route without clone
+    for &edge in route {
+        solution.shaped_tsns.remove(edge, tsn);
+        solution.mark_dirty(edge);
+    }
+}
+
+fn insert_shaped_tsn(solution: &mut Solution, tsn: usize, kth: usize) {
+    let flowtable = solution.flowtable();
+    let route = flowtable.candidate(tsn, kth); // kth_route without clone
+    for &edge in route {
+        solution.shaped_tsns.insert(edge, tsn);
+        solution.mark_dirty(edge);
     }
 }
 
 fn remove_allocated_tsn(solution: &mut Solution, tsn: usize, kth: usize) {
     let flowtable = solution.flowtable();
     let route = flowtable.candidate(tsn, kth); // kth_route without clone
-    let gcl = &mut solution.allocated_tsns;
     for &edge in route {
-        gcl.remove(edge, tsn);
+        solution.allocated_tsns.remove(edge, tsn);
+        solution.mark_dirty(edge);
     }
 }
 
-fn insert_allocated_tsn(solution: &mut Solution, tsn: usize, kth: usize, schedule: Schedule, period: u32) {
+fn insert_allocated_tsn(solution: &mut Solution, tsn: usize, kth: usize,
+    schedule: Schedule, period: u32, isolation: Isolation) {
     let flowtable = solution.flowtable();
     let route = flowtable.candidate(tsn, kth); // kth_route without clone
+    route.iter().for_each(|&edge| solution.mark_dirty(edge));
     let gcl = &mut solution.allocated_tsns;
     let hyperperiod = gcl.hyperperiod();
 
@@ -261,9 +424,14 @@ fn insert_allocated_tsn(solution: &mut Solution, tsn: usize, kth: usize, schedul
                     ..(timeshift + windows[r][f].end);
                 gcl.insert_gate_evt(edge, tsn, window);
                 if r == 0 { continue; }
-                let window = (timeshift + windows[r-1][f].start)
-                    ..(timeshift + windows[r][f].start);
-                gcl.insert_queue_evt(edge, schedule.queue, tsn, window);
+                // stream isolation 以一個事件佔住整個實例待在佇列的期間
+                let arrival = match isolation {
+                    Isolation::Stream if f + 1 < frame_len => continue,
+                    Isolation::Stream => windows[r-1][0].start,
+                    _                 => windows[r-1][f].start,
+                };
+                let window = (timeshift + arrival)..(timeshift + windows[r][f].start);
+                gcl.insert_queue_evt(edge, schedule.queue, tsn, window, isolation);
             }
         }
     }
@@ -271,10 +439,12 @@ fn insert_allocated_tsn(solution: &mut Solution, tsn: usize, kth: usize, schedul
 
 #[cfg(test)]
 mod tests {
+    use super::{compare_tsn, insert_allocated_tsn, OrderBy, Schedule};
     use crate::cnc::CNC;
-    use crate::component::GateCtrlList;
+    use crate::component::{GateCtrlList, Isolation};
     use crate::network::Network;
-    use crate::utils::stream::TSN;
+    use crate::component::Objective;
+    use crate::utils::stream::{AVB, TSN};
     use crate::utils::yaml;
 
     fn setup() -> CNC {
@@ -298,16 +468,129 @@ mod tests {
         cnc
     }
 
+    #[test]
+    fn it_keeps_stream_instance_alone_in_queue() {
+        let cnc = setup();
+        let flowtable = cnc.solution.flowtable();
+        let route = flowtable.candidate(2, 0).clone();
+        let vacant_between_frames = |isolation| {
+            let mut solution = cnc.solution.clone();
+            solution.allocated_tsns = GateCtrlList::new(&cnc.network, 200);
+            // the second frame leaves the source 25μs after the first one has gone through
+            let mut schedule = Schedule::new(&route, 3000, 0, 0);
+            schedule.windows = vec![vec![0..15, 40..55], vec![15..30, 55..70]];
+            insert_allocated_tsn(&mut solution, 2, 0, schedule, 200, isolation);
+            solution.allocated_tsns.is_queue_vacant(route[1], 0, 20..35, isolation)
+        };
+        assert!(vacant_between_frames(Isolation::Frame));
+        assert!(!vacant_between_frames(Isolation::Stream));
+    }
+
+    #[test]
+    fn it_chooses_offset_within_window() {
+        let mut network = Network::new();
+        network.add_nodes(2, 0);
+        network.add_edges(vec![(0, 1, 100.0)]);
+        let tsns = vec![
+            TSN::new(0, 1, 1500, 100, 16, 0),
+            TSN { offset_max: Some(40), ..TSN::new(0, 1, 1500, 100, 20, 0) },
+        ];
+        let config = yaml::load_config("data/config/default.yaml");
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(tsns, vec![]);
+        cnc.solution.allocated_tsns = GateCtrlList::new(&cnc.network, 100);
+        cnc.scheduler.configure(&mut cnc.solution);
+        assert!(cnc.solution.outcome(1).is_schedulable());
+        assert_eq!(cnc.solution.offset(0), 0);
+        assert_eq!(cnc.solution.offset(1), 11);
+    }
+
+    #[test]
+    fn it_tries_every_offset_within_window() {
+        let mut network = Network::new();
+        network.add_nodes(2, 0);
+        network.add_edges(vec![(0, 1, 100.0)]);
+        let tsns = vec![TSN { offset_max: Some(16), ..TSN::new(0, 1, 1500, 100, 16, 0) }];
+        let config = yaml::load_config("data/config/default.yaml");
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(tsns, vec![]);
+        // GCL: occupied by others at 1..16 and 32..47, only offset 16 fits
+        let mut gcl = GateCtrlList::new(&cnc.network, 100);
+        gcl.insert_gate_evt(0.into(), 1, 1..16);
+        gcl.insert_gate_evt(0.into(), 2, 32..47);
+        cnc.solution.allocated_tsns = gcl;
+        let schedule = cnc.scheduler.try_release_windows(0, 0, &cnc.solution).unwrap();
+        assert_eq!(schedule.offset, 16);
+    }
+
+    #[test]
+    fn it_rejects_avbs_beyond_share() {
+        let mut network = Network::new();
+        network.add_nodes(2, 0);
+        network.add_edges(vec![(0, 1, 100.0)]);
+        let avbs = vec![
+            AVB::new(0, 1, 5000, 100, 1000, 'A'),
+            AVB::new(0, 1, 3000, 100, 1000, 'A'),
+            AVB::new(0, 1, 2000, 100, 1000, 'B'),
+        ];
+        let config = yaml::load_config("data/config/default.yaml");
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(vec![], avbs);
+        let mut solution = cnc.solution.clone();
+        cnc.scheduler.configure(&mut solution);
+        // 50 + 30 bytes/μs exceeds 75% of the link, while 50 + 20 does not
+        assert!(solution.outcome(0).is_schedulable());
+        assert!(solution.outcome(1).is_unschedulable());
+        assert!(solution.outcome(2).is_schedulable());
+        assert_eq!(solution.traversed_avbs.iter(0.into()).collect::<Vec<_>>(), [0, 2]);
+        let objs = cnc.evaluator.evaluate_objectives(&solution, &cnc.solution);
+        assert_eq!(objs.get(Objective::AVBRejected), 1.0);
+        assert_eq!(objs.get(Objective::AVBFailed), 0.0);
+    }
+
+    #[test]
+    fn it_orders_tsns_by_policy() {
+        let cnc = setup();
+        let flowtable = cnc.solution.flowtable();
+        let sorted = |order| {
+            let mut tsns = vec![0, 1, 2, 3];
+            tsns.sort_by(|&t1, &t2| compare_tsn(t1, t2, order, &cnc.solution, &flowtable));
+            tsns
+        };
+        assert_eq!(sorted(OrderBy::Deadline), [0, 1, 2, 3]);
+        assert_eq!(sorted(OrderBy::Size), [1, 3, 2, 0]);
+        assert_eq!(sorted(OrderBy::Utilization), [1, 0, 2, 3]);
+    }
+
     #[test]
     fn it_calculates_windows() {
         let mut cnc = setup();
         let network = cnc.network;
         cnc.solution.allocated_tsns = GateCtrlList::new(&network, 60);
-        let result = cnc.scheduler.try_calculate_windows(0, 0, &cnc.solution);
+        let result = cnc.scheduler.try_calculate_windows(0, 0, 0, &cnc.solution);
         let windows = result.unwrap().windows;
         assert_eq!(windows, [[0..15], [15..30]]);
-        let result = cnc.scheduler.try_calculate_windows(2, 0, &cnc.solution);
+        let result = cnc.scheduler.try_calculate_windows(2, 0, 0, &cnc.solution);
         let windows = result.unwrap().windows;
         assert_eq!(windows, [[0..15, 15..30], [15..30, 30..45]]);
     }
+
+    #[test]
+    fn it_shortens_guard_band_on_preemptable_links() {
+        let mut cnc = setup();
+        let mut network = (*cnc.network).clone();
+        let mut first_windows = |network: &Network| {
+            let mut gcl = GateCtrlList::new(network, 60);
+            gcl.reserve_guard_bands(network);
+            cnc.solution.allocated_tsns = gcl;
+            let result = cnc.scheduler.try_calculate_windows(0, 0, 0, &cnc.solution);
+            let windows = result.unwrap().windows;
+            (windows[0][0].clone(), windows[1][0].clone())
+        };
+        // a full MTU of 15μs is reserved ahead of each window
+        assert_eq!(first_windows(&network), (0..30, 30..60));
+        network.enable_preemption(0, 1);
+        // 143 bytes of fragment take 1.43μs, rounded up to a guard band of 2μs
+        assert_eq!(first_windows(&network), (0..17, 17..47));
+    }
 }
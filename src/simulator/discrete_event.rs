@@ -0,0 +1,560 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+use std::ops::Range;
+
+use num::integer::lcm;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+
+use crate::component::{Evaluator, Solution};
+use crate::network::{EdgeIndex, Path, MAX_FRAGMENT, MTU};
+
+/// 模擬結果與分析上界的差距在此範圍內，視為浮點誤差
+const TOLERANCE: f64 = 1e-6;
+
+/// 佇列的流量類別，依優先權由高到低排列；TAS 整形的 TSN 資料流只在自己的 GCL 窗口中傳輸
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    TAS,
+    ATS,
+    A,
+    B,
+    BE,
+}
+
+/// 在 GCL 窗口以外，依嚴格優先權競爭連線的類別
+const SHARED_CLASSES: [Class; 4] = [Class::ATS, Class::A, Class::B, Class::BE];
+
+impl Class {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// * `stream` - 所屬的資料流，BE 背景流量為 None
+/// * `release` - 所屬訊息的釋出時間，用於計算端對端延遲
+/// * `eligible` - ATS 整形後，或 TAS 的 GCL 窗口開啟後，可以被送出的時間
+#[derive(Clone, Debug)]
+struct Frame {
+    stream: Option<usize>,
+    class: Class,
+    size: u32,
+    release: f64,
+    eligible: f64,
+    hop: usize,
+}
+
+#[derive(Debug)]
+enum Action {
+    Arrive(EdgeIndex, Frame),
+    Finish(EdgeIndex),
+    Depart(EdgeIndex, Frame),
+    Wake(EdgeIndex),
+}
+
+#[derive(Debug)]
+struct Event {
+    time: f64,
+    seq: usize,
+    action: Action,
+}
+
+/// 每個輸出埠的狀態
+/// * `closures` - 整個模擬期間 TAS 閘門佔用的時段，其它流量在此期間不能傳輸
+/// * `credits` - class A 及 class B 的 CBS 信用
+/// * `idle_slopes` - class A 及 class B 的 idle slope，即經過此埠的資料流所保留的頻寬
+/// * `transmitting` - 正在傳輸的非 TAS 封包，可被 GCL 窗口搶占而暫停
+/// * `express_until` - TAS 封包傳完的時間
+struct Port {
+    bandwidth: f64,
+    preemptable: bool,
+    closures: Vec<Range<f64>>,
+    queues: [VecDeque<Frame>; 5],
+    credits: [f64; 2],
+    idle_slopes: [f64; 2],
+    transmitting: Option<Frame>,
+    express_until: f64,
+    updated: f64,
+}
+
+/// 以離散事件模擬驗證分析所得的延遲上界
+/// * `hyperperiods` - 模擬的超週期數
+/// * `be_load` - 每條連線上 BE 背景流量佔頻寬的比例
+/// * `seed` - BE 背景流量的亂數種子
+pub struct Simulator {
+    hyperperiods: u32,
+    be_load: f64,
+    seed: u64,
+}
+
+/// 一條資料流的模擬最大延遲與分析上界
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub stream: usize,
+    pub frames: usize,
+    pub simulated: f64,
+    pub bound: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    pub records: Vec<Record>,
+}
+
+impl Simulator {
+    pub fn new(hyperperiods: u32, be_load: f64, seed: u64) -> Self {
+        Simulator { hyperperiods, be_load, seed }
+    }
+    /// 重播數個超週期的流量，回傳每條資料流所有封包的端對端延遲
+    pub fn simulate(&self, solution: &Solution) -> Vec<Vec<f64>> {
+        let flowtable = solution.flowtable();
+        let network = solution.network();
+        let gcl = &solution.allocated_tsns;
+        let mut rng = ChaChaRng::seed_from_u64(self.seed);
+
+        let hyperperiod = flowtable.tsns().iter()
+            .map(|&tsn| flowtable.tsn_spec(tsn).period)
+            .chain(flowtable.avbs().iter().map(|&avb| flowtable.avb_spec(avb).period))
+            .fold(1, lcm);
+        let horizon = hyperperiod * self.hyperperiods;
+        let routes = (0..flowtable.len())
+            .map(|nth| {
+                let kth = solution.selection(nth).next().unwrap();
+                flowtable.candidate(nth, kth).clone()
+            })
+            .collect::<Vec<_>>();
+        let mut latencies = vec![vec![]; flowtable.len()];
+
+        // NOTE GCL 中只記錄每條資料流第一個實例的窗口，在此依週期展開到整個模擬期間
+        let mut closures = vec![vec![]; network.edge_count()];
+        let mut gates = vec![vec![]; flowtable.len()];
+        for &tsn in flowtable.tsns() {
+            let spec = flowtable.tsn_spec(tsn);
+            if spec.is_shaped() || !solution.outcome(tsn).is_schedulable() { continue; }
+            let offset = solution.offset(tsn);
+            for &edge in routes[tsn].iter() {
+                let windows = gcl.get_stream_windows(edge, tsn).into_iter()
+                    .filter(|window| window.start < offset + spec.period)
+                    .collect::<Vec<_>>();
+                let mut starts = vec![];
+                for time_shift in (0..horizon).step_by(spec.period as usize) {
+                    closures[edge.index()].extend(windows.iter()
+                        .map(|w| (time_shift + w.start) as f64..(time_shift + w.end) as f64));
+                    starts.extend(windows.iter().map(|w| (time_shift + w.start) as f64));
+                }
+                starts.sort_by(|x, y| x.partial_cmp(y).unwrap());
+                gates[tsn].push(VecDeque::from(starts));
+            }
+        }
+
+        let mut ports = (0..network.edge_count())
+            .map(|ix| {
+                let edge = EdgeIndex::from(ix);
+                // 802.1Qav：每個 SR class 的 idle slope 為該 class 的資料流實際保留的頻寬
+                let mut idle_slopes = [0.0; 2];
+                for avb in solution.traversed_avbs.iter(edge) {
+                    let spec = flowtable.avb_spec(avb);
+                    let i = if spec.class == 'A' { 0 } else { 1 };
+                    idle_slopes[i] += spec.size as f64 / spec.period as f64;
+                }
+                let mut windows = std::mem::take(&mut closures[ix]);
+                windows.sort_by(|x: &Range<f64>, y| x.start.partial_cmp(&y.start).unwrap());
+                Port {
+                    bandwidth: network.bandwidth_on(edge),
+                    preemptable: network.is_preemptable(edge),
+                    closures: merge(windows),
+                    queues: Default::default(),
+                    credits: [0.0; 2],
+                    idle_slopes,
+                    transmitting: None,
+                    express_until: 0.0,
+                    updated: 0.0,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut heap = BinaryHeap::new();
+        let mut seq = 0;
+        let mut push = |heap: &mut BinaryHeap<Event>, time: f64, action: Action| {
+            seq += 1;
+            heap.push(Event { time, seq, action });
+        };
+
+        let mut release = |heap: &mut BinaryHeap<Event>, nth: usize, class: Class,
+            size: u32, period: u32, offset: u32| {
+            let first = routes[nth][0];
+            for time_shift in (0..horizon).step_by(period as usize) {
+                let time = (time_shift + offset) as f64;
+                let mut remained = size;
+                while remained > 0 {
+                    let size = remained.min(MTU);
+                    remained -= size;
+                    let frame = Frame {
+                        stream: Some(nth), class, size, release: time, eligible: time, hop: 0,
+                    };
+                    push(heap, time, Action::Arrive(first, frame));
+                }
+            }
+        };
+        for &tsn in flowtable.tsns() {
+            let spec = flowtable.tsn_spec(tsn);
+            let class = match spec.is_shaped() {
+                true  => Class::ATS,
+                false if solution.outcome(tsn).is_schedulable() => Class::TAS,
+                false => continue,
+            };
+            release(&mut heap, tsn, class, spec.size, spec.period, solution.offset(tsn));
+        }
+        for &avb in flowtable.avbs() {
+            if !solution.outcome(avb).is_schedulable() { continue; }
+            let spec = flowtable.avb_spec(avb);
+            let class = if spec.class == 'A' { Class::A } else { Class::B };
+            release(&mut heap, avb, class, spec.size, spec.period, 0);
+        }
+        if self.be_load > 0.0 {
+            for (ix, port) in ports.iter().enumerate() {
+                let interval = MTU as f64 / (self.be_load * port.bandwidth);
+                let mut time = rng.gen::<f64>() * interval;
+                while time < horizon as f64 {
+                    let frame = Frame {
+                        stream: None, class: Class::BE, size: MTU, release: time, eligible: time, hop: 0,
+                    };
+                    push(&mut heap, time, Action::Arrive(ix.into(), frame));
+                    time += interval * rng.gen_range(0.5..1.5);
+                }
+            }
+        }
+
+        // ATS 的 interleaved regulator，記錄每條資料流在每一跳上次可以送出的時間
+        let mut regulated = vec![vec![]; flowtable.len()];
+        for &tsn in flowtable.tsns() {
+            regulated[tsn] = vec![f64::NEG_INFINITY; routes[tsn].len()];
+        }
+
+        while let Some(Event { time, action, .. }) = heap.pop() {
+            let edge = match action {
+                Action::Arrive(edge, mut frame) => {
+                    match (frame.class, frame.stream) {
+                        (Class::ATS, Some(tsn)) => {
+                            let spec = flowtable.tsn_spec(tsn);
+                            let rate = spec.size as f64 / spec.period as f64;
+                            let last = &mut regulated[tsn][frame.hop];
+                            frame.eligible = f64::max(time, *last + frame.size as f64 / rate);
+                            *last = frame.eligible;
+                        }
+                        (Class::TAS, Some(tsn)) => {
+                            // 錯過的窗口不會再開啟，封包只能等到下一個窗口
+                            let starts = &mut gates[tsn][frame.hop];
+                            while starts.front().is_some_and(|&start| start < time - TOLERANCE) {
+                                starts.pop_front();
+                            }
+                            frame.eligible = starts.pop_front().unwrap_or(f64::INFINITY);
+                        }
+                        _ => (),
+                    }
+                    let port = &mut ports[edge.index()];
+                    port.update(time);
+                    port.queues[frame.class.index()].push_back(frame);
+                    edge
+                }
+                Action::Finish(edge) => {
+                    let port = &mut ports[edge.index()];
+                    port.update(time);
+                    let frame = port.transmitting.take().unwrap();
+                    if let Some(action) = forward(frame, time, &routes, &mut latencies) {
+                        push(&mut heap, time, action);
+                    }
+                    edge
+                }
+                Action::Depart(edge, frame) => {
+                    ports[edge.index()].update(time);
+                    if let Some(action) = forward(frame, time, &routes, &mut latencies) {
+                        push(&mut heap, time, action);
+                    }
+                    edge
+                }
+                Action::Wake(edge) => {
+                    ports[edge.index()].update(time);
+                    edge
+                }
+            };
+            match ports[edge.index()].try_express(time) {
+                Some(Ok((finish, frame))) => push(&mut heap, finish, Action::Depart(edge, frame)),
+                Some(Err(wake))           => push(&mut heap, wake, Action::Wake(edge)),
+                None                      => (),
+            }
+            match ports[edge.index()].try_transmit(time) {
+                Some(Ok(finish)) => push(&mut heap, finish, Action::Finish(edge)),
+                Some(Err(wake))  => push(&mut heap, wake, Action::Wake(edge)),
+                None             => (),
+            }
+        }
+        latencies
+    }
+    /// 比較每條資料流的模擬最大延遲與分析上界
+    pub fn validate(&self, solution: &Solution, evaluator: &Evaluator) -> Report {
+        let flowtable = solution.flowtable();
+        let latencies = self.simulate(solution);
+        let mut records = vec![];
+        for &tsn in flowtable.tsns() {
+            let bound = match flowtable.tsn_spec(tsn).is_shaped() {
+                true  => Some(evaluator.evaluate_tsn_ats_bound(tsn, solution)),
+                false => evaluator.evaluate_tsn_timing(tsn, solution).map(|t| t.latency),
+            };
+            if let Some(bound) = bound {
+                records.push(Record::new(tsn, &latencies[tsn], bound));
+            }
+        }
+        for &avb in flowtable.avbs() {
+            if !solution.outcome(avb).is_schedulable() { continue; }
+            let bound = evaluator.evaluate_avb_wcd(avb, solution);
+            records.push(Record::new(avb, &latencies[avb], bound));
+        }
+        Report { records }
+    }
+}
+
+impl Port {
+    /// 將 CBS 信用更新到 `now`；閘門關閉期間信用凍結
+    fn update(&mut self, now: f64) {
+        let opened = self.opened_between(self.updated, now);
+        for (i, &class) in [Class::A, Class::B].iter().enumerate() {
+            let idle_slope = self.idle_slopes[i];
+            let sending = matches!(&self.transmitting, Some(frame) if frame.class == class);
+            let credit = &mut self.credits[i];
+            if sending {
+                *credit += (idle_slope - self.bandwidth) * opened;
+            } else if !self.queues[class.index()].is_empty() {
+                *credit += idle_slope * opened;
+            } else {
+                *credit = f64::min(0.0, *credit + idle_slope * opened);
+            }
+        }
+        self.updated = now;
+    }
+    /// 在 TAS 封包的窗口開啟時送出，不受其它封包影響（被搶占或已由 guard band 淨空）。
+    /// 回傳 `Ok((傳完的時間, 封包))`，或 `Err(窗口開啟的時間)`
+    fn try_express(&mut self, now: f64) -> Option<Result<(f64, Frame), f64>> {
+        let frame = self.queues[Class::TAS.index()].front()?;
+        if self.express_until > now + TOLERANCE { return None; }
+        if frame.eligible > now {
+            return frame.eligible.is_finite().then_some(Err(frame.eligible));
+        }
+        let frame = self.queues[Class::TAS.index()].pop_front().unwrap();
+        self.express_until = now + frame.size as f64 / self.bandwidth;
+        Some(Ok((self.express_until, frame)))
+    }
+    /// 在 GCL 窗口以外依嚴格優先權挑選下一個封包。
+    /// 回傳 `Ok(傳完的時間)`，或 `Err(下次可以再嘗試的時間)`
+    fn try_transmit(&mut self, now: f64) -> Option<Result<f64, f64>> {
+        if self.transmitting.is_some() { return None; }
+        if let Some(closure) = self.closure_at(now) {
+            let pending = SHARED_CLASSES.iter().any(|class| !self.queues[class.index()].is_empty());
+            return if pending { Some(Err(closure.end)) } else { None };
+        }
+        let next_closure = self.next_closure(now);
+        let mut wake = f64::INFINITY;
+        for &class in SHARED_CLASSES.iter() {
+            let frame = match self.queues[class.index()].front() {
+                Some(frame) => frame,
+                None        => continue,
+            };
+            if frame.eligible > now {
+                wake = wake.min(frame.eligible);
+                continue;
+            }
+            if let Some(i) = [Class::A, Class::B].iter().position(|&c| c == class) {
+                if self.credits[i] < -TOLERANCE {
+                    wake = wake.min(now - self.credits[i] / self.idle_slopes[i]);
+                    continue;
+                }
+            }
+            let transmit = frame.size as f64 / self.bandwidth;
+            let blocking = match self.preemptable {
+                true  => transmit.min(MAX_FRAGMENT as f64 / self.bandwidth),
+                false => transmit,
+            };
+            if let Some(closure) = &next_closure {
+                if now + blocking > closure.start {
+                    wake = wake.min(closure.end);
+                    continue;
+                }
+            }
+            let frame = self.queues[class.index()].pop_front().unwrap();
+            self.transmitting = Some(frame);
+            return Some(Ok(self.advance_opened(now, transmit)));
+        }
+        match wake.is_finite() {
+            true  => Some(Err(wake)),
+            false => None,
+        }
+    }
+    fn closure_at(&self, time: f64) -> Option<Range<f64>> {
+        let ix = self.closures.partition_point(|closure| closure.end <= time);
+        self.closures.get(ix)
+            .filter(|closure| closure.start <= time)
+            .cloned()
+    }
+    fn next_closure(&self, time: f64) -> Option<Range<f64>> {
+        let ix = self.closures.partition_point(|closure| closure.start < time);
+        self.closures.get(ix).cloned()
+    }
+    fn opened_between(&self, start: f64, end: f64) -> f64 {
+        let closed: f64 = self.closures.iter()
+            .skip(self.closures.partition_point(|closure| closure.end <= start))
+            .take_while(|closure| closure.start < end)
+            .map(|closure| closure.end.min(end) - closure.start.max(start))
+            .sum();
+        end - start - closed
+    }
+    /// 從 `start` 起算，經過 `duration` 的開啟時間後的時刻（被搶占的封包會在閘門關閉期間暫停）
+    fn advance_opened(&self, start: f64, duration: f64) -> f64 {
+        let mut time = start;
+        let mut remained = duration;
+        let skipped = self.closures.partition_point(|closure| closure.end <= start);
+        for closure in self.closures.iter().skip(skipped) {
+            if time + remained <= closure.start { break; }
+            remained -= closure.start - time;
+            time = closure.end;
+        }
+        time + remained
+    }
+}
+
+/// 封包傳完後送往下一跳；已抵達終點者記錄其端對端延遲
+fn forward(mut frame: Frame, time: f64, routes: &[Path], latencies: &mut [Vec<f64>]) -> Option<Action> {
+    let nth = frame.stream?;
+    match frame.hop + 1 < routes[nth].len() {
+        true  => {
+            frame.hop += 1;
+            Some(Action::Arrive(routes[nth][frame.hop], frame))
+        }
+        false => {
+            latencies[nth].push(time - frame.release);
+            None
+        }
+    }
+}
+
+fn merge(windows: Vec<Range<f64>>) -> Vec<Range<f64>> {
+    let mut merged: Vec<Range<f64>> = vec![];
+    for window in windows {
+        match merged.last_mut() {
+            Some(last) if last.end >= window.start => last.end = last.end.max(window.end),
+            _ => merged.push(window),
+        }
+    }
+    merged
+}
+
+impl Record {
+    fn new(stream: usize, latencies: &[f64], bound: u32) -> Self {
+        let simulated = latencies.iter().cloned().fold(0.0, f64::max);
+        Record { stream, frames: latencies.len(), simulated, bound }
+    }
+    pub fn is_violated(&self) -> bool {
+        self.simulated > self.bound as f64 + TOLERANCE
+    }
+}
+
+impl Report {
+    pub fn violations(&self) -> impl Iterator<Item=&Record> {
+        self.records.iter().filter(|record| record.is_violated())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "simulated against analytic bounds")?;
+        for record in &self.records {
+            writeln!(f, "- stream #{:02} simulated {:.2} over {} frames, bounded by {}{}",
+                     record.stream, record.simulated, record.frames, record.bound,
+                     if record.is_violated() { " (violated)" } else { "" })?;
+        }
+        writeln!(f, "the bounds are violated by {} streams", self.violations().count())
+    }
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Event {}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Event {
+    /// BinaryHeap 是最大堆積，因此時間早者、先加入者要比較大
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.time.partial_cmp(&self.time).unwrap()
+            .then(other.seq.cmp(&self.seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cnc::CNC;
+    use crate::network::Network;
+    use crate::utils::stream::{AVB, TSN};
+    use crate::utils::yaml;
+
+    #[test]
+    fn it_shapes_avb_by_credit() {
+        let mut network = Network::new();
+        network.add_nodes(3, 0);
+        network.add_edges(vec![(0, 1, 100.0), (1, 2, 100.0)]);
+        let avbs = vec![
+            AVB::new(0, 2, 75, 10000, 200, 'A'),
+            AVB::new(0, 2, 150, 10000, 200, 'A'),
+            AVB::new(0, 2, 75, 10000, 200, 'B'),
+        ];
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.analysis = String::from("cbs");
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(vec![], avbs);
+        let mut solution = cnc.solution.clone();
+        cnc.scheduler.configure(&mut solution);
+        let simulator = Simulator::new(1, 0.0, 0);
+        // class A spends its credit on the first frame and recovers it at the reserved 0.0225 B/μs,
+        // class B transmits in between
+        let latencies = simulator.simulate(&solution);
+        assert_eq!(latencies[0], [1.5]);
+        let recovery = 0.75 * (100.0 - 0.0225) / 0.0225;
+        assert!((latencies[1][0] - (0.75 + recovery + 3.0)).abs() < TOLERANCE);
+        assert_eq!(latencies[2], [2.25]);
+        let report = simulator.validate(&solution, &cnc.evaluator);
+        assert_eq!(report.violations().count(), 0);
+    }
+
+    #[test]
+    fn it_transmits_tas_frames_in_their_windows() {
+        let mut network = Network::new();
+        network.add_nodes(3, 0);
+        network.add_edges(vec![(0, 1, 100.0), (1, 2, 100.0)]);
+        let tsns = vec![TSN::new(0, 2, 3000, 1000, 1000, 0)];
+        let avbs = vec![AVB::new(0, 2, 1500, 1000, 1000, 'A')];
+        let mut config = yaml::load_config("data/config/default.yaml");
+        config.analysis = String::from("cbs");
+        let mut cnc = CNC::new(network, config);
+        cnc.add_streams(tsns, avbs);
+        let mut solution = cnc.solution.clone();
+        cnc.scheduler.configure(&mut solution);
+        let simulator = Simulator::new(2, 0.5, 0);
+        let latencies = simulator.simulate(&solution);
+        // two instances of two MTU frames, each sent in its own window on both hops
+        assert_eq!(latencies[0].len(), 4);
+        let timing = cnc.evaluator.evaluate_tsn_timing(0, &solution).unwrap();
+        assert!(latencies[0].iter().all(|&latency| latency <= timing.latency as f64));
+        let report = simulator.validate(&solution, &cnc.evaluator);
+        assert_eq!(report.violations().count(), 0);
+    }
+}
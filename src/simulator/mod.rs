@@ -0,0 +1,3 @@
+mod discrete_event;
+
+pub use discrete_event::{Record, Report, Simulator};
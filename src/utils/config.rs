@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
@@ -9,6 +12,7 @@ pub struct Args {
     pub flag_config: Option<String>,
     pub flag_algorithm: Option<String>,
     pub flag_memory: Option<f64>,
+    pub flag_param: Option<String>,
     pub flag_seed: Option<u64>,
 }
 
@@ -16,17 +20,167 @@ pub struct Args {
 pub struct Config {
     pub name: String,
     pub algorithm: String,
-    pub weights: [f64; 4],
+    #[serde(default)]
+    pub objectives: BTreeMap<String, f64>,
+    /// 舊版設定檔的四個權重，依序為 TSN 失敗、AVB 失敗、重新路由及 AVB 最差延遲
+    #[serde(default)]
+    pub weights: Option<[f64; 4]>,
     pub early_stop: bool,
     pub timeout: u64,
     pub seed: u64,
+    #[serde(default = "default_isolation")]
+    pub isolation: String,
+    #[serde(default = "default_queue_depth")]
+    pub queue_depth: usize,
+    #[serde(default = "default_orderings")]
+    pub orderings: Vec<String>,
+    #[serde(default = "default_analysis")]
+    pub analysis: String,
+    #[serde(default = "default_avb_shares")]
+    pub avb_shares: BTreeMap<char, f64>,
+    #[serde(default = "default_cooling")]
+    pub cooling: String,
+    #[serde(default = "default_cooling_rate")]
+    pub cooling_rate: f64,
+    #[serde(default = "default_temperature")]
+    pub temperature: f64,
+    #[serde(default = "default_crossover")]
+    pub crossover: String,
+    #[serde(default = "default_greedy_order")]
+    pub greedy_order: String,
+    #[serde(default = "default_greedy_criterion")]
+    pub greedy_criterion: String,
+    #[serde(default)]
+    pub root_bridge: Option<usize>,
+    #[serde(default = "default_ro_scope")]
+    pub ro_scope: String,
+    #[serde(default)]
+    pub pareto_front: Option<String>,
+    #[serde(default)]
+    pub simulate: u32,
+    #[serde(default)]
+    pub be_load: f64,
+    /// 是否在 TSN 窗口前保留 guard band，預設不保留以便和舊實驗比較
+    #[serde(default)]
+    pub guard_band: bool,
     pub parameters: Parameters,
 }
 
+/// ACO 的參數，未指定者使用預設值
+/// * `tsn_memory`, `avb_memory` - 上一次選用的路徑之能見度倍率
+/// * `m` - 每一代的螞蟻數
+/// * `l` - 每一代依排名留下費洛蒙的螞蟻數
+/// * `tao0` - 初始費洛蒙
+/// * `rho` - 費洛蒙蒸發率
+/// * `q0` - 直接選擇最佳路徑（而非依機率選擇）的機率
+/// * `max_ph`, `min_ph` - 費洛蒙的上下限
+/// * `deposit` - 排名第一的是這一代的最佳解 `iteration_best` 或歷史最佳解 `global_best`
+/// * `stagnation` - 歷史最佳解連續幾代沒有進步時重設費洛蒙，0 代表不重設
+/// * `threads` - 同時建構螞蟻的執行緒數，0 代表使用所有核心；不影響結果
 #[derive(Deserialize, Clone, Debug)]
 pub struct Parameters {
     pub tsn_memory: f64,
     pub avb_memory: f64,
+    #[serde(default = "default_m")]
+    pub m: usize,
+    #[serde(default = "default_l")]
+    pub l: usize,
+    #[serde(default = "default_tao0")]
+    pub tao0: f64,
+    #[serde(default = "default_rho")]
+    pub rho: f64,
+    #[serde(default = "default_q0")]
+    pub q0: f64,
+    #[serde(default = "default_max_ph")]
+    pub max_ph: f64,
+    #[serde(default = "default_min_ph")]
+    pub min_ph: f64,
+    #[serde(default = "default_deposit")]
+    pub deposit: String,
+    #[serde(default)]
+    pub stagnation: usize,
+    #[serde(default)]
+    pub threads: usize,
+}
+
+fn default_isolation() -> String {
+    String::from("legacy")
+}
+
+fn default_queue_depth() -> usize {
+    1
+}
+
+fn default_orderings() -> Vec<String> {
+    vec![String::from("deadline")]
+}
+
+fn default_analysis() -> String {
+    String::from("legacy")
+}
+
+fn default_avb_shares() -> BTreeMap<char, f64> {
+    vec![('A', 0.75), ('B', 0.75)].into_iter().collect()
+}
+
+fn default_cooling() -> String {
+    String::from("geometric")
+}
+
+fn default_cooling_rate() -> f64 {
+    0.95
+}
+
+fn default_temperature() -> f64 {
+    100.0
+}
+
+fn default_crossover() -> String {
+    String::from("uniform")
+}
+
+fn default_greedy_order() -> String {
+    String::from("deadline")
+}
+
+fn default_greedy_criterion() -> String {
+    String::from("load")
+}
+
+fn default_ro_scope() -> String {
+    String::from("avb")
+}
+
+fn default_m() -> usize {
+    60
+}
+
+fn default_l() -> usize {
+    1
+}
+
+fn default_tao0() -> f64 {
+    1.0
+}
+
+fn default_rho() -> f64 {
+    0.5
+}
+
+fn default_q0() -> f64 {
+    0.0
+}
+
+fn default_max_ph() -> f64 {
+    1.0
+}
+
+fn default_min_ph() -> f64 {
+    0.003
+}
+
+fn default_deposit() -> String {
+    String::from("iteration_best")
 }
 
 impl Config {
@@ -38,8 +192,80 @@ impl Config {
             self.parameters.tsn_memory = num::clamp(flag, 0.0, 9999999.9);
             self.parameters.avb_memory = num::clamp(flag, 0.0, 9999999.9);
         }
+        if let Some(flag) = args.flag_param {
+            for pair in flag.split(',').filter(|pair| !pair.is_empty()) {
+                let (name, value) = pair.split_once('=')
+                    .expect("Failed specify an ACO parameter without value");
+                self.parameters.set(name.trim(), value.trim());
+            }
+        }
         if let Some(flag) = args.flag_seed {
             self.seed = flag;
         }
     }
 }
+
+impl Parameters {
+    /// 以名稱覆寫一個參數
+    pub fn set(&mut self, name: &str, value: &str) {
+        let float = || value.parse::<f64>()
+            .unwrap_or_else(|_| panic!("Failed specify a non-numeric ACO parameter {}", name));
+        let count = || value.parse::<usize>()
+            .unwrap_or_else(|_| panic!("Failed specify a non-integer ACO parameter {}", name));
+        match name {
+            "tsn_memory" => self.tsn_memory = float(),
+            "avb_memory" => self.avb_memory = float(),
+            "m"          => self.m = count(),
+            "l"          => self.l = count(),
+            "tao0"       => self.tao0 = float(),
+            "rho"        => self.rho = float(),
+            "q0"         => self.q0 = float(),
+            "max_ph"     => self.max_ph = float(),
+            "min_ph"     => self.min_ph = float(),
+            "deposit"    => self.deposit = value.to_string(),
+            "stagnation" => self.stagnation = count(),
+            "threads"    => self.threads = count(),
+            _            => panic!("Failed specify an unknown ACO parameter {}", name),
+        }
+    }
+    /// 檢查參數的範圍，不合理時直接結束
+    pub fn validate(&self) {
+        assert!(self.tsn_memory >= 0.0 && self.avb_memory >= 0.0,
+                "Failed specify negative ACO memory");
+        assert!(self.m >= 1, "Failed specify ACO m less than 1");
+        assert!(1 <= self.l && self.l <= self.m, "Failed specify ACO l out of [1, m]");
+        assert!(0.0 <= self.rho && self.rho <= 1.0, "Failed specify ACO rho out of [0, 1]");
+        assert!(0.0 <= self.q0 && self.q0 <= 1.0, "Failed specify ACO q0 out of [0, 1]");
+        assert!(0.0 < self.min_ph && self.min_ph <= self.max_ph,
+                "Failed specify ACO pheromone bounds with 0 < min_ph <= max_ph");
+        assert!(self.min_ph <= self.tao0 && self.tao0 <= self.max_ph,
+                "Failed specify ACO tao0 out of [min_ph, max_ph]");
+    }
+}
+
+impl fmt::Display for Parameters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{tsn_memory: {}, avb_memory: {}, m: {}, l: {}, tao0: {}, rho: {}, q0: {}, \
+                   max_ph: {}, min_ph: {}, deposit: {}, stagnation: {}, threads: {}}}",
+               self.tsn_memory, self.avb_memory, self.m, self.l, self.tao0, self.rho, self.q0,
+               self.max_ph, self.min_ph, self.deposit, self.stagnation, self.threads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::yaml;
+
+    #[test]
+    fn it_overrides_aco_parameters() {
+        let mut config = yaml::load_config("data/config/default.yaml");
+        assert_eq!(config.parameters.m, 60);
+        config.parameters.set("rho", "0.3");
+        config.parameters.set("m", "40");
+        config.parameters.validate();
+        assert_eq!(config.parameters.rho, 0.3);
+        assert_eq!(config.parameters.m, 40);
+        config.parameters.set("q0", "1.5");
+        assert!(std::panic::catch_unwind(|| config.parameters.validate()).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::RangeInclusive;
+
 use serde::Deserialize;
 
 #[derive(Deserialize, Clone)]
@@ -7,7 +9,14 @@ pub struct TSN {
     pub size: u32,
     pub period: u32,
     pub deadline: u32,
-    pub offset: u32,
+    #[serde(alias = "offset")]
+    pub offset_min: u32,
+    #[serde(default)]
+    pub offset_max: Option<u32>,
+    #[serde(default)]
+    pub shaper: Shaper,
+    #[serde(default)]
+    pub jitter: Option<u32>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -20,11 +29,39 @@ pub struct AVB {
     pub class: char,
 }
 
+/// TSN 資料流的整形方式
+/// * `TAS` - Time-Aware Shaper (802.1Qbv)，需由排程器配置 GCL
+/// * `ATS` - Asynchronous Traffic Shaping (802.1Qcr)，不需時間同步，僅以延遲上界檢驗
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Shaper {
+    #[default]
+    TAS,
+    ATS,
+}
+
 
 impl TSN {
     pub fn new(src: usize, dst: usize, size: u32, period: u32,
                deadline: u32, offset: u32) -> Self {
-        TSN { src, dst, size, period, deadline, offset }
+        let shaper = Shaper::TAS;
+        TSN {
+            src, dst, size, period, deadline,
+            offset_min: offset, offset_max: None, shaper, jitter: None,
+        }
+    }
+    /// 資料流可以被釋出的時間窗口，未指定 `offset_max` 者即固定於 `offset_min`
+    pub fn offsets(&self) -> RangeInclusive<u32> {
+        self.offset_min..=self.offset_max.unwrap_or(self.offset_min)
+    }
+    /// 檢查釋出窗口落在一個週期之內，且 `offset_max` 不小於 `offset_min`
+    pub fn validate(&self) {
+        let offset_max = self.offset_max.unwrap_or(self.offset_min);
+        assert!(self.offset_min <= offset_max, "Failed specify a TSN offset_max less than offset_min");
+        assert!(offset_max < self.period, "Failed specify a TSN offset_max beyond its period");
+    }
+    pub fn is_shaped(&self) -> bool {
+        self.shaper == Shaper::ATS
     }
 }
 
@@ -22,6 +22,8 @@ struct NetworkScaleYaml {
 struct NetworkEdgeYaml {
     ends: [usize; 2],
     bandwidth: f64,
+    #[serde(default)]
+    preemption: bool,
 }
 
 #[derive(Deserialize)]
@@ -48,7 +50,14 @@ pub fn load_network(path: &str) -> Network {
     let switches = yaml.scale.end_devices + yaml.scale.bridges;
     debug_assert_eq!(switches, check_switches(&yaml));
     network.add_nodes(yaml.scale.end_devices, yaml.scale.bridges);
+    let preemptables = yaml.edges.iter()
+        .filter(|e| e.preemption)
+        .map(|e| e.ends)
+        .collect::<Vec<_>>();
     network.add_edges(flatten(yaml.edges));
+    for [end0, end1] in preemptables {
+        network.enable_preemption(end0, end1);
+    }
     network
 }
 
@@ -61,6 +70,7 @@ pub fn load_streams(path: &str, fold: u32) -> (Vec<TSN>, Vec<AVB>) {
     debug_assert_eq!(yaml.scale.avbs, yaml.avbs.len());
     debug_assert_eq!(yaml.scale.hyperperiod, check_hyperperiod(&yaml));
     debug_assert_eq!(yaml.scale.end_devices, check_end_devices(&yaml));
+    yaml.tsns.iter().for_each(TSN::validate);
     (repeated(yaml.tsns, fold), repeated(yaml.avbs, fold))
 }
 
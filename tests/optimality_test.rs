@@ -0,0 +1,45 @@
+use adams_leaf::algorithm::AlgorithmEnum;
+use adams_leaf::cnc::CNC;
+use adams_leaf::utils::yaml;
+
+/// 在小型實例上以分支界限法求得最佳解，回報其它演算法與最佳解的差距
+///
+/// 各演算法都跑滿時限，結果隨機器速度而異，因此不放進 integration_test 的輸出比對。
+/// 分支界限法可能需要跑上一分鐘，請以 `cargo test --release -- --ignored` 執行。
+#[test]
+#[ignore]
+fn it_reports_optimality_gap() {
+    let oracle = configure_light("bnb", 60_000_000);
+    // 超過時限時只是目前找到的最佳解，不能作為比較的基準
+    assert!(matches!(&oracle.algorithm, AlgorithmEnum::BNB(bnb) if bnb.is_optimal()));
+    let evaluator = &oracle.evaluator;
+    let (optimum, _) = evaluator.evaluate_cost_objectives(&oracle.solution, oracle.latest());
+    for &algorithm in &["aco", "ecmp", "ga", "nsga2", "ro", "sa", "spf", "stp", "tabu"] {
+        let cnc = configure_light(algorithm, 100_000);
+        // 以同一組權重計算成本，RO 自己的評估器不計入重新路由
+        let (cost, _) = evaluator.evaluate_cost_objectives(&cnc.solution, cnc.latest());
+        eprintln!("--- {} optimality gap: {:.2}% ---", algorithm, (cost - optimum) / optimum * 100.0);
+        assert!(cost >= optimum);
+    }
+}
+
+/// 回傳設定兩次後的 CNC，不提早結束搜尋
+fn configure_light(algorithm: &str, timeout: u64) -> CNC {
+    let (tsns1, avbs1) = yaml::load_streams("data/streams/motiv-light.yaml", 1);
+    let (tsns2, avbs2) = yaml::load_streams("data/streams/motiv-light.yaml", 1);
+    let network = yaml::load_network("data/network/typical.yaml");
+
+    let mut config = yaml::load_config("data/config/default.yaml");
+    config.algorithm = String::from(algorithm);
+    config.timeout = timeout;
+    config.early_stop = false;
+
+    let mut cnc = CNC::new(network, config);
+
+    cnc.add_streams(tsns1, avbs1);
+    cnc.configure();
+
+    cnc.add_streams(tsns2, avbs2);
+    cnc.configure();
+    cnc
+}